@@ -0,0 +1,319 @@
+use nih_plug::prelude::Enum;
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+/// Time constant of the running band energy that onsets are measured against
+const ONSET_AVERAGE_SECONDS: f32 = 0.25;
+/// Band energy below this counts as silence and never triggers an onset
+const ONSET_ENERGY_FLOOR: f32 = 1e-6;
+/// Cap on the reported onset strength, coming out of silence the ratio is meaningless
+const ONSET_MAX_STRENGTH: f32 = 100.0;
+/// Level that maps to the bottom of the 0-1 range, anything quieter is silence
+const LEVEL_FLOOR_DB: f32 = -60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnsetBand {
+    Low,
+    Mid,
+    High,
+}
+
+impl OnsetBand {
+    pub const ALL: [OnsetBand; 3] = [OnsetBand::Low, OnsetBand::Mid, OnsetBand::High];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OnsetBand::Low => "low",
+            OnsetBand::Mid => "mid",
+            OnsetBand::High => "high",
+        }
+    }
+
+    /// Where onsets of the band go under the address base
+    pub fn address(self) -> &'static str {
+        match self {
+            OnsetBand::Low => "onset/low",
+            OnsetBand::Mid => "onset/mid",
+            OnsetBand::High => "onset/high",
+        }
+    }
+
+    /// Minimum time between two onsets in a band, kicks ring out a lot longer than hats
+    pub fn refractory_seconds(self) -> f32 {
+        match self {
+            OnsetBand::Low => 0.15,
+            OnsetBand::Mid => 0.1,
+            OnsetBand::High => 0.05,
+        }
+    }
+}
+
+/// Which input channels the analysis listens to, independent of what is streamed as audio
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisChannel {
+    #[name = "Sum"]
+    Sum,
+    #[name = "Left"]
+    Left,
+    #[name = "Right"]
+    Right,
+}
+
+impl AnalysisChannel {
+    /// Falls back to all channels when the one asked for isn't there, a mono input has no right
+    pub fn select<T>(self, channels: &[T]) -> &[T] {
+        let index = match self {
+            AnalysisChannel::Sum => return channels,
+            AnalysisChannel::Left => 0,
+            AnalysisChannel::Right => 1,
+        };
+        channels.get(index..index + 1).unwrap_or(channels)
+    }
+}
+
+/// How an RMS level is scaled to 0..1 before it is sent, linear levels sit near zero for
+/// everything but the loudest parts
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelMapping {
+    /// `LEVEL_FLOOR_DB`..0 dB, follows loudness the closest
+    #[name = "dB"]
+    Decibels,
+    /// Lifts the low range without throwing away the quiet parts like the dB floor does
+    #[name = "Sqrt"]
+    Sqrt,
+    #[name = "Linear"]
+    Linear,
+}
+
+impl LevelMapping {
+    pub fn apply(self, rms: f32) -> f32 {
+        let level = match self {
+            LevelMapping::Decibels => {
+                let db = 20.0 * rms.max(f32::MIN_POSITIVE).log10();
+                1.0 - db / LEVEL_FLOOR_DB
+            }
+            LevelMapping::Sqrt => rms.sqrt(),
+            LevelMapping::Linear => rms,
+        };
+        level.clamp(0.0, 1.0)
+    }
+}
+
+pub struct OnsetSettings {
+    /// Crossover between the low and mid band in Hz
+    pub low_edge: f32,
+    /// Crossover between the mid and high band in Hz
+    pub high_edge: f32,
+    /// 0.0 - 1.0 per band, indexed like `OnsetBand::ALL`
+    pub sensitivity: [f32; 3],
+}
+
+/// RBJ cookbook biquad in transposed direct form II
+#[derive(Default, Clone, Copy)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    fn lowpass(sample_rate: f32, frequency: f32) -> Self {
+        let (cos, alpha) = Self::prepare(sample_rate, frequency);
+        Self::normalized(
+            (1.0 - cos) / 2.0,
+            1.0 - cos,
+            (1.0 - cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    fn highpass(sample_rate: f32, frequency: f32) -> Self {
+        let (cos, alpha) = Self::prepare(sample_rate, frequency);
+        Self::normalized(
+            (1.0 + cos) / 2.0,
+            -(1.0 + cos),
+            (1.0 + cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    fn prepare(sample_rate: f32, frequency: f32) -> (f32, f32) {
+        let frequency = frequency.clamp(1.0, sample_rate * 0.45);
+        let w0 = 2.0 * PI * frequency / sample_rate;
+        (w0.cos(), w0.sin() / (2.0 * FRAC_1_SQRT_2))
+    }
+
+    fn normalized(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    /// Swap in new coefficients without resetting the filter state
+    fn retune(&mut self, other: Biquad) {
+        *self = Biquad {
+            z1: self.z1,
+            z2: self.z2,
+            ..other
+        };
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct BandState {
+    energy: f32,
+    average: f32,
+    refractory_samples: usize,
+}
+
+/// Splits the input into low/mid/high bands and reports when the energy in a band jumps well
+/// above its recent average
+pub struct OnsetDetector {
+    sample_rate: f32,
+    low_edge: f32,
+    high_edge: f32,
+    low_pass: Biquad,
+    mid_high_pass: Biquad,
+    mid_low_pass: Biquad,
+    high_pass: Biquad,
+    bands: [BandState; 3],
+}
+
+impl OnsetDetector {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            low_edge: 0.0,
+            high_edge: 0.0,
+            low_pass: Biquad::default(),
+            mid_high_pass: Biquad::default(),
+            mid_low_pass: Biquad::default(),
+            high_pass: Biquad::default(),
+            bands: [BandState::default(); 3],
+        }
+    }
+
+    fn update_filters(&mut self, settings: &OnsetSettings) {
+        if settings.low_edge == self.low_edge && settings.high_edge == self.high_edge {
+            return;
+        }
+        self.low_edge = settings.low_edge;
+        self.high_edge = settings.high_edge;
+        self.low_pass
+            .retune(Biquad::lowpass(self.sample_rate, self.low_edge));
+        self.mid_high_pass
+            .retune(Biquad::highpass(self.sample_rate, self.low_edge));
+        self.mid_low_pass
+            .retune(Biquad::lowpass(self.sample_rate, self.high_edge));
+        self.high_pass
+            .retune(Biquad::highpass(self.sample_rate, self.high_edge));
+    }
+
+    /// Analyze one block of audio, `on_onset` is called with the band and how far the energy
+    /// jumped above the average
+    pub fn process(
+        &mut self,
+        channels: &[&mut [f32]],
+        settings: &OnsetSettings,
+        mut on_onset: impl FnMut(OnsetBand, f32),
+    ) {
+        let num_samples = channels.first().map_or(0, |channel| channel.len());
+        if num_samples == 0 {
+            return;
+        }
+        self.update_filters(settings);
+
+        for band in &mut self.bands {
+            band.energy = 0.0;
+        }
+        for i in 0..num_samples {
+            let sample = channels.iter().map(|channel| channel[i]).sum::<f32>()
+                / channels.len() as f32;
+            let low = self.low_pass.process(sample);
+            let mid = self
+                .mid_low_pass
+                .process(self.mid_high_pass.process(sample));
+            let high = self.high_pass.process(sample);
+            self.bands[0].energy += low * low;
+            self.bands[1].energy += mid * mid;
+            self.bands[2].energy += high * high;
+        }
+
+        let block_seconds = num_samples as f32 / self.sample_rate;
+        let average_coefficient = 1.0 - (-block_seconds / ONSET_AVERAGE_SECONDS).exp();
+        for (index, band) in OnsetBand::ALL.into_iter().enumerate() {
+            let state = &mut self.bands[index];
+            let energy = state.energy / num_samples as f32;
+            //Full sensitivity triggers at 1.5x the average energy, zero sensitivity needs 7.5x
+            let ratio = 1.5 + (1.0 - settings.sensitivity[index].clamp(0.0, 1.0)) * 6.0;
+
+            state.refractory_samples = state.refractory_samples.saturating_sub(num_samples);
+            if state.refractory_samples == 0
+                && energy > ONSET_ENERGY_FLOOR
+                && energy > state.average * ratio
+            {
+                state.refractory_samples =
+                    (band.refractory_seconds() * self.sample_rate) as usize;
+                on_onset(
+                    band,
+                    (energy / state.average.max(ONSET_ENERGY_FLOOR)).min(ONSET_MAX_STRENGTH),
+                );
+            }
+            state.average += (energy - state.average) * average_coefficient;
+        }
+    }
+}
+
+/// RMS level of the input over fixed windows, for following the level at a slower rate than
+/// the host buffers come in
+#[derive(Default)]
+pub struct LevelFollower {
+    sum_squares: f32,
+    samples: usize,
+}
+
+impl LevelFollower {
+    /// `on_level` is called with the sample offset and level of every window that completes in
+    /// this block, the level is mapped to 0..1 with `mapping`
+    pub fn process(
+        &mut self,
+        channels: &[&mut [f32]],
+        window_samples: usize,
+        mapping: LevelMapping,
+        mut on_level: impl FnMut(u32, f32),
+    ) {
+        let num_samples = channels.first().map_or(0, |channel| channel.len());
+        let window_samples = window_samples.max(1);
+        for i in 0..num_samples {
+            let square = channels.iter().map(|channel| channel[i] * channel[i]).sum::<f32>();
+            self.sum_squares += square / channels.len() as f32;
+            self.samples += 1;
+            if self.samples >= window_samples {
+                let rms = (self.sum_squares / self.samples as f32).sqrt();
+                on_level(i as u32, mapping.apply(rms));
+                self.sum_squares = 0.0;
+                self.samples = 0;
+            }
+        }
+    }
+}
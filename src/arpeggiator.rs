@@ -0,0 +1,217 @@
+use nih_plug::prelude::Enum;
+
+/// Enough for every note on one channel, more only allocates in the audio thread
+const HELD_NOTES_CAPACITY: usize = 128;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArpPattern {
+    #[name = "Up"]
+    Up,
+    #[name = "Down"]
+    Down,
+    /// Up and back down without playing the top and bottom notes twice
+    #[name = "Up/Down"]
+    UpDown,
+    #[name = "Random"]
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArpNote {
+    pub channel: u8,
+    pub note: u8,
+    pub velocity: f32,
+}
+
+/// Steps through the held notes one tick at a time, the ticks come from the caller so the
+/// arpeggiator doesn't need to know about tempo
+pub struct Arpeggiator {
+    /// Sorted by note, lowest first
+    held: Vec<ArpNote>,
+    step: usize,
+    /// The note that got a note on and still needs its note off
+    sounding: Option<ArpNote>,
+    random_state: u32,
+}
+
+impl Default for Arpeggiator {
+    fn default() -> Self {
+        Self {
+            held: Vec::with_capacity(HELD_NOTES_CAPACITY),
+            step: 0,
+            sounding: None,
+            random_state: 0x9e37_79b9,
+        }
+    }
+}
+
+impl Arpeggiator {
+    pub fn note_on(&mut self, note: ArpNote) {
+        self.note_off(note.channel, note.note);
+        let index = self.held.partition_point(|held| held.note <= note.note);
+        self.held.insert(index, note);
+    }
+
+    /// Whether the note was held, a note off for one that isn't belongs to a note played
+    /// before the arpeggiator was turned on
+    pub fn note_off(&mut self, channel: u8, note: u8) -> bool {
+        let count = self.held.len();
+        self.held.retain(|held| held.channel != channel || held.note != note);
+        self.held.len() != count
+    }
+
+    /// Move to the next note of the pattern, returns the note to turn off and the one to turn on
+    pub fn tick(
+        &mut self,
+        pattern: ArpPattern,
+        octaves: usize,
+    ) -> (Option<ArpNote>, Option<ArpNote>) {
+        let off = self.sounding.take();
+        let length = self.held.len() * octaves.max(1);
+        if length == 0 {
+            self.step = 0;
+            return (off, None);
+        }
+        let index = match pattern {
+            ArpPattern::Up => self.step % length,
+            ArpPattern::Down => length - 1 - self.step % length,
+            ArpPattern::UpDown if length > 1 => {
+                let position = self.step % (2 * length - 2);
+                if position < length {
+                    position
+                } else {
+                    2 * length - 2 - position
+                }
+            }
+            ArpPattern::UpDown => 0,
+            ArpPattern::Random => self.next_random() as usize % length,
+        };
+        self.step = self.step.wrapping_add(1);
+        let held = self.held[index % self.held.len()];
+        let octave = (index / self.held.len()) as u8;
+        //Octaves that would go past the top of the MIDI range play the held note instead
+        let note = held.note.checked_add(12 * octave).filter(|note| *note < 128);
+        let on = ArpNote {
+            note: note.unwrap_or(held.note),
+            ..held
+        };
+        self.sounding = Some(on);
+        (off, Some(on))
+    }
+
+    /// Stop playing, returns the note that still needs a note off. Held notes are kept so the
+    /// pattern picks up again on the next tick, `clear` forgets them as well
+    pub fn stop(&mut self) -> Option<ArpNote> {
+        self.step = 0;
+        self.sounding.take()
+    }
+
+    pub fn clear(&mut self) -> Option<ArpNote> {
+        self.held.clear();
+        self.stop()
+    }
+
+    /// xorshift, good enough to pick notes and never allocates or locks
+    fn next_random(&mut self) -> u32 {
+        let mut x = self.random_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.random_state = x;
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn held(notes: &[u8]) -> Arpeggiator {
+        let mut arpeggiator = Arpeggiator::default();
+        for &note in notes {
+            arpeggiator.note_on(ArpNote {
+                channel: 0,
+                note,
+                velocity: 1.0,
+            });
+        }
+        arpeggiator
+    }
+
+    /// The notes turned on by the next `count` ticks
+    fn play(
+        arpeggiator: &mut Arpeggiator,
+        pattern: ArpPattern,
+        octaves: usize,
+        count: usize,
+    ) -> Vec<u8> {
+        (0..count)
+            .map(|_| arpeggiator.tick(pattern, octaves).1.unwrap().note)
+            .collect()
+    }
+
+    #[test]
+    fn up_plays_lowest_first() {
+        let mut arpeggiator = held(&[67, 60, 64]);
+        assert_eq!(play(&mut arpeggiator, ArpPattern::Up, 1, 4), vec![60, 64, 67, 60]);
+    }
+
+    #[test]
+    fn down_plays_highest_first() {
+        let mut arpeggiator = held(&[67, 60, 64]);
+        assert_eq!(play(&mut arpeggiator, ArpPattern::Down, 1, 4), vec![67, 64, 60, 67]);
+    }
+
+    #[test]
+    fn up_down_turns_around_without_repeats() {
+        let mut arpeggiator = held(&[60, 64, 67]);
+        assert_eq!(
+            play(&mut arpeggiator, ArpPattern::UpDown, 1, 7),
+            vec![60, 64, 67, 64, 60, 64, 67]
+        );
+        let mut arpeggiator = held(&[60]);
+        assert_eq!(play(&mut arpeggiator, ArpPattern::UpDown, 1, 2), vec![60, 60]);
+    }
+
+    #[test]
+    fn random_plays_held_notes_and_their_octaves() {
+        let mut arpeggiator = held(&[60, 64]);
+        for note in play(&mut arpeggiator, ArpPattern::Random, 2, 32) {
+            assert!([60, 64, 72, 76].contains(&note));
+        }
+    }
+
+    #[test]
+    fn octaves_past_the_top_play_the_held_note() {
+        let mut arpeggiator = held(&[60, 120]);
+        assert_eq!(play(&mut arpeggiator, ArpPattern::Up, 2, 4), vec![60, 120, 72, 120]);
+    }
+
+    #[test]
+    fn ticks_turn_off_the_last_note() {
+        let mut arpeggiator = held(&[60, 64]);
+        let (off, on) = arpeggiator.tick(ArpPattern::Up, 1);
+        assert_eq!(off, None);
+        assert_eq!(arpeggiator.tick(ArpPattern::Up, 1).0, on);
+    }
+
+    #[test]
+    fn held_notes_can_change_between_ticks() {
+        let mut arpeggiator = held(&[60, 64]);
+        assert_eq!(play(&mut arpeggiator, ArpPattern::Up, 1, 2), vec![60, 64]);
+        arpeggiator.note_on(ArpNote {
+            channel: 0,
+            note: 67,
+            velocity: 1.0,
+        });
+        assert_eq!(play(&mut arpeggiator, ArpPattern::Up, 1, 2), vec![67, 60]);
+        assert!(arpeggiator.note_off(0, 60));
+        assert!(arpeggiator.note_off(0, 64));
+        //The step is past the one note left, it wraps around instead of indexing out of range
+        assert_eq!(play(&mut arpeggiator, ArpPattern::Down, 1, 2), vec![67, 67]);
+        assert!(arpeggiator.note_off(0, 67));
+        let (off, on) = arpeggiator.tick(ArpPattern::Up, 1);
+        assert_eq!(off.map(|note| note.note), Some(67));
+        assert_eq!(on, None);
+    }
+}
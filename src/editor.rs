@@ -1,78 +1,305 @@
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender};
 use nih_plug::debug::*;
-use nih_plug::prelude::Editor;
+use nih_plug::prelude::{Editor, GuiContext, ParamSetter};
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::ViziaTheming;
 use nih_plug_vizia::{assets, create_vizia_editor, ViziaState};
+use parking_lot::RwLock;
+use rosc::OscType;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::subviews::{ParamView, SettingsView};
-use crate::{OsClapParams, OscAddressBaseType, OscChannelMessageType, OscConnectionType};
+use crate::analysis::OnsetBand;
+use crate::handshake::HandshakeState;
+use crate::schema;
+use crate::subviews::{ArpView, AudioView, ParamView, ReceivedView, SettingsView};
+use crate::transport::{self, OscTransportMode, SendStats};
+use crate::{
+    format_osc_address_base, osc_param_value, param_address_name,
+    param_min_interval, param_osc_name, param_receive_osc_name, sanitize_osc_address, OsClapParams,
+    OscAddressBaseType, OscChannelMessageType, OscConnectionType, OscCustomType, OscAllNotesOffType, OscParamType, NamedCc,
+    OscParamAddressesType,
+    AudioMode, CategoryMask, ConnectionStatus, MessageCategory, ParamActivity, ParamConfig, ParamSendMode, ProcessTiming,
+    AUDIO_BLOCK_FRAMES, MAX_AUDIO_CHANNELS, NUM_PARAMS,
+};
 
 /// VIZIA uses points instead of pixels for text
 const POINT_SCALE: f32 = 0.75;
 
+/// How often the editor wakes up to handle time based work
+const EDITOR_TICK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the process timing readout is refreshed, also the window it averages over
+const PROCESS_TIMING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often dropped messages are added up in the log, a busy channel logs once per interval
+const DROPPED_LOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the send rate readout is refreshed, also the window it averages over
+const SEND_RATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the bandwidth estimate is worked out again
+const BANDWIDTH_ESTIMATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a param row stays lit after its value was sent
+const PARAM_ACTIVITY_HOLD: Duration = Duration::from_millis(200);
+
+/// Received messages shown, the oldest are dropped past this
+const RECEIVED_SHOWN: usize = 100;
+
+/// How long the reset button waits for the confirming second click
+const RESET_CONFIRM_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Height of a row in the views, sliders are the tallest widgets in them
+const ROW_HEIGHT: u32 = 30;
+
+/// Room for the title above the views
+const TITLE_HEIGHT: u32 = 60;
+
+/// All views side by side
+const WINDOW_WIDTH: u32 = 1920;
+
+/// Taller content scrolls instead, so the window still fits on a laptop screen
+const MAX_WINDOW_HEIGHT: u32 = 900;
+
+/// Clocked params are estimated at this tempo
+const TEMPO_ESTIMATE: f32 = 120.0;
+
+/// Params outside of sample accurate mode and per buffer analysis are sent at most once per
+/// buffer, this is the rate for 512 sample buffers at 48kHz
+const BUFFER_RATE_ESTIMATE: f32 = 94.0;
+
+/// Both ways between the editor and the worker
+pub(crate) struct EditorChannels {
+    pub sender: Arc<Sender<OscChannelMessageType>>,
+    /// Param values the worker received
+    pub incoming: Receiver<OscChannelMessageType>,
+    /// Every message the worker received
+    pub received: Receiver<OscChannelMessageType>,
+    /// Messages the audio thread dropped because the channel was full
+    pub dropped: Arc<AtomicU64>,
+    /// What the worker sent
+    pub send_stats: Arc<SendStats>,
+}
+
 #[derive(Lens)]
 struct OsClapEditor {
     sender: Arc<Sender<OscChannelMessageType>>,
+    incoming: Receiver<OscChannelMessageType>,
+    received_source: Receiver<OscChannelMessageType>,
     params: Arc<OsClapParams>,
+    gui_context: Arc<dyn GuiContext>,
     settings: OscSettings,
-    log: Vec<String>
+    log: Vec<String>,
+    /// Source, address and args of the last received messages, oldest first
+    received: Vec<String>,
+    /// Time of the last connection edit, the change is applied once editing has settled
+    pending_connection_change: Option<Instant>,
+    /// Set by the first click on the reset button, the second click within the timeout resets
+    reset_armed: Option<Instant>,
+    /// Worst case network load for the current settings
+    bandwidth_estimate: String,
+    /// `None` until the first estimate, which is made on the first tick
+    last_bandwidth_estimate: Option<Instant>,
+    handshake_state: Arc<RwLock<HandshakeState>>,
+    /// What the receiver told us in the handshake
+    receiver_status: String,
+    connection_status_source: Arc<RwLock<ConnectionStatus>>,
+    /// Last connection status put in the log, so every change is logged once
+    connection_status: ConnectionStatus,
+    param_activity: Arc<ParamActivity>,
+    /// Which params were sent within `PARAM_ACTIVITY_HOLD`
+    param_active: [bool; NUM_PARAMS],
+    process_timing: Arc<ProcessTiming>,
+    /// Average and max process time per buffer over the last `PROCESS_TIMING_INTERVAL`
+    process_time: String,
+    last_process_timing: Instant,
+    dropped: Arc<AtomicU64>,
+    last_dropped_check: Instant,
+    send_stats: Arc<SendStats>,
+    /// Totals at the last refresh of `send_rate`
+    last_send_totals: (u64, u64, u64),
+    last_send_rate_check: Instant,
+    /// Packets and bytes per second over the last `SEND_RATE_INTERVAL` and failed sends
+    send_rate: String,
+    /// `OsClapParams::settings_loads` the settings were last read at
+    settings_loads: u32,
 }
 
 pub struct OscSettings {
     pub osc_server_address: String,
     pub osc_server_port: u16,
+    /// What is typed in the port field, only committed to `osc_server_port` on enter or blur
+    pub osc_server_port_text: String,
+    /// Why the typed port can't be committed
+    pub port_error: Option<String>,
+    /// 0 when nothing is received
+    pub osc_recv_port: u16,
+    /// `host:port` of every destination next to the server
+    pub extra_destinations: Vec<String>,
+    pub osc_bind_address: String,
+    /// 0 when the OS picks the port
+    pub osc_bind_port: u16,
+    pub osc_baud_rate: u32,
     pub osc_address_base: String,
+    pub osc_transport: OscTransportMode,
+    pub param_configs: [ParamConfig; NUM_PARAMS],
+    /// Blank for the param's own name
+    pub param_addresses: [String; NUM_PARAMS],
+    /// Blank to receive under the name the param is sent under
+    pub param_receive_addresses: [String; NUM_PARAMS],
+    pub named_ccs: [NamedCc; 3],
+    /// What the destination receives
+    pub osc_categories: CategoryMask,
+    pub custom_address: String,
+    pub custom_args: String,
+    /// Where Export Schema writes to
+    pub schema_path: String,
+    /// Why the edited destination can't be connected to
+    pub destination_error: Option<String>,
 }
 
 pub enum OsClapEditorEvent {
     SetOscServerAddress(String),
-    SetOscServerPort(u16),
+    SetOscServerPort(String),
+    CommitOscServerPort,
+    SetOscRecvPort(u16),
+    AddExtraDestination,
+    SetExtraDestination(usize, String),
+    RemoveExtraDestination(usize),
+    SetOscBindAddress(String),
+    SetOscBindPort(u16),
+    SetOscBaudRate(u32),
     SetOscAddressBase(String),
+    CycleOscTransport,
     ConnectionChange,
     AddressBaseChange,
+    ResetParams,
+    CycleParamSendMode(usize),
+    ToggleParamBipolar(usize),
+    ToggleParamOsc(usize),
+    ToggleParamMidi(usize),
+    SetParamCc(usize, u8),
+    CycleComparatorEdge(usize),
+    SetComparatorThreshold(usize, f32),
+    SetParamInterval(usize, u32),
+    CycleParamExpression(usize),
+    ToggleCategory(MessageCategory),
+    ToggleNamedCc(usize),
+    SetNamedCc(usize, u8),
+    SetParamAddress(usize, String),
+    ParamAddressChange(usize),
+    SetParamReceiveAddress(usize, String),
+    ParamReceiveAddressChange(usize),
+    CopyParamAddress(usize),
+    CopyAddressBase,
+    SetCustomAddress(String),
+    SetCustomArgs(String),
+    SendCustom,
+    AllNotesOff,
+    SendMeta,
+    ResetSendStats,
+    ClearReceived,
+    SetSchemaPath(String),
+    ExportSchema,
+    Tick,
 }
 
 impl Model for OsClapEditor {
-    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
         event.map(|app_event, _| match app_event {
+            //Edits only update the editor copy, they are committed on ConnectionChange/AddressBaseChange
             OsClapEditorEvent::SetOscServerAddress(ip) => {
                 nih_trace!("Edit Event {}", ip);
                 self.settings.osc_server_address = ip.clone();
-                *self.params.osc_server_address.write() = self.settings.osc_server_address.clone();
+                self.validate_destination();
+                self.delay_connection_change();
+            }
+            OsClapEditorEvent::SetOscServerPort(text) => {
+                nih_trace!("Edit Event {}", text);
+                self.settings.port_error = transport::parse_port(text).err();
+                self.settings.osc_server_port_text = text.clone();
             }
-            OsClapEditorEvent::SetOscServerPort(port) => {
+            OsClapEditorEvent::CommitOscServerPort => {
+                match transport::parse_port(&self.settings.osc_server_port_text) {
+                    Ok(port) => {
+                        self.settings.port_error = None;
+                        self.settings.osc_server_port = port;
+                        self.validate_destination();
+                        self.pending_connection_change = Some(Instant::now());
+                        if self.connection_debounce().is_zero() {
+                            self.apply_connection_change();
+                        }
+                    }
+                    Err(e) => self.log.push(format!("Port not changed: {}", e)),
+                }
+            }
+            OsClapEditorEvent::SetOscRecvPort(port) => {
                 nih_trace!("Edit Event {}", port);
-                self.settings.osc_server_port = port.clone();
-                *self.params.osc_server_port.write() = self.settings.osc_server_port.clone();
+                self.settings.osc_recv_port = *port;
+                self.delay_connection_change();
+            }
+            OsClapEditorEvent::AddExtraDestination => {
+                self.settings.extra_destinations.push(String::new());
+            }
+            OsClapEditorEvent::SetExtraDestination(index, destination) => {
+                nih_trace!("Edit Event {}", destination);
+                if let Some(extra) = self.settings.extra_destinations.get_mut(*index) {
+                    *extra = destination.clone();
+                }
+                self.delay_connection_change();
+            }
+            OsClapEditorEvent::RemoveExtraDestination(index) => {
+                if *index < self.settings.extra_destinations.len() {
+                    self.settings.extra_destinations.remove(*index);
+                }
+            }
+            OsClapEditorEvent::SetOscBindAddress(address) => {
+                nih_trace!("Edit Event {}", address);
+                self.settings.osc_bind_address = address.clone();
+                self.delay_connection_change();
+            }
+            OsClapEditorEvent::SetOscBindPort(port) => {
+                nih_trace!("Edit Event {}", port);
+                self.settings.osc_bind_port = *port;
+                self.delay_connection_change();
+            }
+            OsClapEditorEvent::SetOscBaudRate(baud_rate) => {
+                nih_trace!("Edit Event {}", baud_rate);
+                self.settings.osc_baud_rate = *baud_rate;
+                self.delay_connection_change();
             }
             OsClapEditorEvent::SetOscAddressBase(address) => {
                 nih_trace!("Edit Event {}", address);
                 self.settings.osc_address_base = address.clone();
-                *self.params.osc_address_base.write() = self.settings.osc_address_base.clone();
+            }
+            OsClapEditorEvent::CycleOscTransport => {
+                self.settings.osc_transport = self.settings.osc_transport.next();
+                nih_trace!("Edit Event {}", self.settings.osc_transport);
+                self.validate_destination();
+                self.delay_connection_change();
             }
             OsClapEditorEvent::ConnectionChange => {
-                nih_trace!(
-                    "Connection Changed {}:{}",
-                    self.settings.osc_server_address,
-                    self.settings.osc_server_port
-                );
-                self.log.push(format!("Connecting to: {}:{}", self.settings.osc_server_address, self.settings.osc_server_port));
-                let send_result =
-                    self.sender
-                    .send(OscChannelMessageType::ConnectionChange(OscConnectionType {
-                        ip: self.settings.osc_server_address.clone(),
-                        port: self.settings.osc_server_port,
-                    }));
-                if send_result.is_err() {
-                    nih_error!("Failed to send ConnectionChange update {:?}", send_result.unwrap_err());
-                    self.log.push(format!("Failed change connection"));
+                self.pending_connection_change = Some(Instant::now());
+                if self.connection_debounce().is_zero() {
+                    self.apply_connection_change();
                 }
             }
             OsClapEditorEvent::AddressBaseChange => {
+                let address_base = sanitize_osc_address(&self.settings.osc_address_base);
+                if address_base != self.settings.osc_address_base {
+                    self.log.push(format!(
+                        "Base Address \"{}\" isn't a valid OSC address, using \"{}\"",
+                        self.settings.osc_address_base, address_base
+                    ));
+                    self.settings.osc_address_base = address_base;
+                }
+                *self.params.osc_address_base.write() = self.settings.osc_address_base.clone();
                 nih_trace!("AddressBase Changed: {}", self.settings.osc_address_base);
-                self.log.push(format!("Base Address changed to: {}", self.settings.osc_address_base));
+                self.log.push(format!("Base Address changed to: {}", self.address_base()));
                 let send_result = self.sender.send(OscChannelMessageType::AddressBaseChange(
                     OscAddressBaseType {
                         address: self.settings.osc_address_base.clone(),
@@ -83,35 +310,847 @@ impl Model for OsClapEditor {
                     self.log.push(format!("Failed to update base address"));
                 }
             }
+            OsClapEditorEvent::ResetParams => {
+                if self.reset_armed.take().is_some() {
+                    self.reset_params();
+                } else {
+                    self.reset_armed = Some(Instant::now());
+                }
+            }
+            OsClapEditorEvent::CycleParamSendMode(index) => {
+                self.edit_param_config(*index, |config| config.send_mode = config.send_mode.next());
+            }
+            OsClapEditorEvent::ToggleParamBipolar(index) => {
+                self.edit_param_config(*index, |config| config.bipolar = !config.bipolar);
+            }
+            OsClapEditorEvent::ToggleParamOsc(index) => {
+                self.edit_param_config(*index, |config| config.route.osc = !config.route.osc);
+            }
+            OsClapEditorEvent::ToggleParamMidi(index) => {
+                self.edit_param_config(*index, |config| config.route.midi = !config.route.midi);
+            }
+            OsClapEditorEvent::SetParamCc(index, cc) => {
+                self.edit_param_config(*index, |config| config.route.cc = *cc);
+            }
+            OsClapEditorEvent::CycleComparatorEdge(index) => {
+                self.edit_param_config(*index, |config| {
+                    config.comparator.edge = config.comparator.edge.next()
+                });
+            }
+            OsClapEditorEvent::SetComparatorThreshold(index, threshold) => {
+                self.edit_param_config(*index, |config| config.comparator.threshold = *threshold);
+            }
+            OsClapEditorEvent::SetParamInterval(index, interval) => {
+                self.edit_param_config(*index, |config| config.min_interval_ms = *interval);
+            }
+            OsClapEditorEvent::CycleParamExpression(index) => {
+                self.edit_param_config(*index, |config| {
+                    config.expression = config.expression.next()
+                });
+            }
+            OsClapEditorEvent::ToggleCategory(category) => {
+                self.settings.osc_categories.toggle(*category);
+                let categories = self.settings.osc_categories;
+                *self.params.osc_categories.write() = categories;
+                let state = if categories.allows(*category) { "on" } else { "off" };
+                self.log.push(format!("{} {}", category.name(), state));
+                let send_result =
+                    self.sender.send(OscChannelMessageType::CategoriesChange(categories));
+                if let Err(e) = send_result {
+                    nih_error!("Failed to send CategoriesChange update {:?}", e);
+                    self.log.push("Failed to update categories".to_string());
+                }
+            }
+            OsClapEditorEvent::ToggleNamedCc(index) => {
+                self.edit_named_cc(*index, |named_cc| named_cc.enabled = !named_cc.enabled);
+            }
+            OsClapEditorEvent::SetNamedCc(index, cc) => {
+                self.edit_named_cc(*index, |named_cc| named_cc.cc = *cc);
+            }
+            OsClapEditorEvent::SetParamAddress(index, address) => {
+                if let Some(param_address) = self.settings.param_addresses.get_mut(*index) {
+                    *param_address = address.clone();
+                }
+            }
+            OsClapEditorEvent::ParamAddressChange(index) => {
+                *self.params.param_addresses.write() = self.settings.param_addresses.clone();
+                self.check_param_address(*index);
+                let send_result = self.sender.send(OscChannelMessageType::ParamAddressesChange(
+                    OscParamAddressesType {
+                        addresses: self.settings.param_addresses.clone(),
+                    },
+                ));
+                if let Err(e) = send_result {
+                    nih_error!("Failed to send ParamAddressesChange update {:?}", e);
+                    self.log.push("Failed to update param address".to_string());
+                }
+            }
+            OsClapEditorEvent::SetParamReceiveAddress(index, address) => {
+                if let Some(receive_address) = self.settings.param_receive_addresses.get_mut(*index)
+                {
+                    *receive_address = address.clone();
+                }
+            }
+            OsClapEditorEvent::ParamReceiveAddressChange(index) => {
+                *self.params.param_receive_addresses.write() =
+                    self.settings.param_receive_addresses.clone();
+                self.check_param_receive_address(*index);
+            }
+            OsClapEditorEvent::CopyParamAddress(index) => {
+                if *index < NUM_PARAMS {
+                    let name = self.param_name(*index);
+                    let address = format!("{}/param/{}", self.address_base(), name);
+                    self.copy_address(cx, address);
+                }
+            }
+            OsClapEditorEvent::CopyAddressBase => {
+                let address = self.address_base();
+                self.copy_address(cx, address);
+            }
+            OsClapEditorEvent::SetCustomAddress(address) => {
+                self.settings.custom_address = address.clone();
+            }
+            OsClapEditorEvent::SetCustomArgs(args) => {
+                self.settings.custom_args = args.clone();
+            }
+            OsClapEditorEvent::SendCustom => self.send_custom(),
+            OsClapEditorEvent::AllNotesOff => self.send_all_notes_off(),
+            OsClapEditorEvent::SendMeta => match self.sender.send(OscChannelMessageType::SendMeta) {
+                Ok(_) => self.log.push("Sent param ranges".to_string()),
+                Err(e) => {
+                    nih_error!("Failed to send param ranges {:?}", e);
+                    self.log.push("Failed to send param ranges".to_string());
+                }
+            },
+            OsClapEditorEvent::ResetSendStats => {
+                self.send_stats.reset();
+                self.last_send_totals = (0, 0, 0);
+                self.last_send_rate_check = Instant::now();
+                self.send_rate = String::new();
+            }
+            OsClapEditorEvent::ClearReceived => self.received.clear(),
+            OsClapEditorEvent::SetSchemaPath(path) => {
+                self.settings.schema_path = path.clone();
+            }
+            OsClapEditorEvent::ExportSchema => self.export_schema(),
+            OsClapEditorEvent::Tick => {
+                self.apply_incoming_params();
+                self.show_received();
+                let settings_loads = self.params.settings_loads.load(Ordering::Acquire);
+                if settings_loads != self.settings_loads {
+                    self.settings_loads = settings_loads;
+                    self.reload_settings();
+                }
+                if let Some(last_edit) = self.pending_connection_change {
+                    if last_edit.elapsed() >= self.connection_debounce() {
+                        self.apply_connection_change();
+                    }
+                }
+                if let Some(armed) = self.reset_armed {
+                    if armed.elapsed() >= RESET_CONFIRM_TIMEOUT {
+                        self.reset_armed = None;
+                    }
+                }
+                //Params can change from the host too so poll instead of tracking every edit
+                if !matches!(
+                    self.last_bandwidth_estimate,
+                    Some(last) if last.elapsed() < BANDWIDTH_ESTIMATE_INTERVAL
+                ) {
+                    self.last_bandwidth_estimate = Some(Instant::now());
+                    let bandwidth_estimate = self.estimate_bandwidth();
+                    if bandwidth_estimate != self.bandwidth_estimate {
+                        self.bandwidth_estimate = bandwidth_estimate;
+                    }
+                }
+                let connection_status = self.connection_status_source.read().clone();
+                if connection_status != self.connection_status {
+                    match &connection_status {
+                        ConnectionStatus::Disconnected => self.log.push("Disconnected".to_string()),
+                        ConnectionStatus::Connected => self.log.push("Connected".to_string()),
+                        ConnectionStatus::ConnectError(e) => {
+                            self.log.push(format!("Disconnected: {}", e))
+                        }
+                    }
+                    self.connection_status = connection_status;
+                }
+                let receiver_status = match &self.connection_status {
+                    ConnectionStatus::Disconnected => "Disconnected".to_string(),
+                    ConnectionStatus::Connected => self.handshake_state.read().to_string(),
+                    ConnectionStatus::ConnectError(e) => e.clone(),
+                };
+                if receiver_status != self.receiver_status {
+                    self.receiver_status = receiver_status;
+                }
+                let param_active = std::array::from_fn(|index| {
+                    self.param_activity
+                        .since_sent(index)
+                        .is_some_and(|since| since < PARAM_ACTIVITY_HOLD)
+                });
+                if param_active != self.param_active {
+                    self.param_active = param_active;
+                }
+                if self.last_process_timing.elapsed() >= PROCESS_TIMING_INTERVAL {
+                    self.last_process_timing = Instant::now();
+                    let process_time = match self.process_timing.take() {
+                        _ if !self.params.flag_process_timing.value() => String::new(),
+                        Some((average, max)) => format!("{}/{} µs", average, max),
+                        None => "No buffers".to_string(),
+                    };
+                    if process_time != self.process_time {
+                        self.process_time = process_time;
+                    }
+                }
+                if self.last_dropped_check.elapsed() >= DROPPED_LOG_INTERVAL {
+                    self.last_dropped_check = Instant::now();
+                    let dropped = self.dropped.swap(0, Ordering::Relaxed);
+                    if dropped > 0 {
+                        self.log.push(format!("{} messages dropped", dropped));
+                    }
+                }
+                if self.last_send_rate_check.elapsed() >= SEND_RATE_INTERVAL {
+                    self.refresh_send_rate();
+                }
+            }
         });
     }
 }
 
+impl OsClapEditor {
+    /// What actually went out since the last refresh, next to the estimate of what could
+    fn refresh_send_rate(&mut self) {
+        let seconds = self.last_send_rate_check.elapsed().as_secs_f64();
+        self.last_send_rate_check = Instant::now();
+        let totals = self.send_stats.totals();
+        let (packets, bytes, errors) = totals;
+        let (last_packets, last_bytes, _) = self.last_send_totals;
+        self.last_send_totals = totals;
+        let packets = packets.saturating_sub(last_packets) as f64 / seconds;
+        let bytes = bytes.saturating_sub(last_bytes) as f64 / seconds;
+        let mut send_rate = format!("{:.0} pkt/s, {:.1} kB/s", packets, bytes / 1000.0);
+        if errors > 0 {
+            send_rate.push_str(&format!(", {} failed", errors));
+        }
+        if send_rate != self.send_rate {
+            self.send_rate = send_rate;
+        }
+    }
+
+    fn connection_debounce(&self) -> Duration {
+        Duration::from_millis(self.params.connection_debounce.value() as u64)
+    }
+
+    /// Restart the debounce timer if a connection change is waiting to be applied
+    fn delay_connection_change(&mut self) {
+        if self.pending_connection_change.is_some() {
+            self.pending_connection_change = Some(Instant::now());
+        }
+    }
+
+    fn edit_param_config(&mut self, index: usize, edit: impl FnOnce(&mut ParamConfig)) {
+        if let Some(config) = self.settings.param_configs.get_mut(index) {
+            edit(config);
+            nih_trace!("Param Config {} {:?}", index, config);
+            *self.params.param_configs.write() = self.settings.param_configs;
+        }
+    }
+
+    fn edit_named_cc(&mut self, index: usize, edit: impl FnOnce(&mut NamedCc)) {
+        if let Some(named_cc) = self.settings.named_ccs.get_mut(index) {
+            edit(named_cc);
+            nih_trace!("Named CC {} {:?}", index, named_cc);
+            *self.params.named_ccs.write() = self.settings.named_ccs;
+        }
+    }
+
+    /// The base the worker is currently sending with, not the one being edited
+    fn address_base(&self) -> String {
+        format_osc_address_base(&self.params.osc_address_base.read())
+    }
+
+    /// Packets and bytes per second with every param moving, notes are left out since they
+    /// depend entirely on what is being played
+    fn estimate_bandwidth(&self) -> String {
+        let address_base = self.address_base();
+        let osc_sample_rate = self.params.osc_sample_rate.value() as f32;
+        let mut packets = 0.0;
+        let mut bytes = 0.0;
+        let mut add = |rate: f32, size: usize| {
+            packets += rate;
+            bytes += rate * size as f32;
+        };
+
+        let categories = self.settings.osc_categories;
+        let clock_ticks = self.params.clock_division.value().ticks_per_beat();
+        let param_rate = if let Some(ticks_per_beat) = clock_ticks {
+            ticks_per_beat as f32 * TEMPO_ESTIMATE / 60.0
+        } else if self.params.flag_sample_accurate.value() {
+            osc_sample_rate
+        } else {
+            BUFFER_RATE_ESTIMATE
+        };
+        if self.params.flag_param_digest.value() && categories.params {
+            let address = format!("{}/params", address_base);
+            let mut size = osc_message_size(&address, NUM_PARAMS);
+            if self.params.flag_digest_names.value() {
+                //Names are padded strings with a type tag of their own
+                size += 8;
+                for index in 0..NUM_PARAMS {
+                    size += (self.param_name(index).len() + 4) & !3;
+                }
+            }
+            add(BUFFER_RATE_ESTIMATE, size);
+        }
+        for (index, config) in self.settings.param_configs.iter().enumerate() {
+            if !config.route.osc || !categories.params || self.params.flag_param_digest.value() {
+                continue;
+            }
+            let suffix = match config.send_mode {
+                ParamSendMode::Absolute | ParamSendMode::Comparator | ParamSendMode::Toggle => "",
+                ParamSendMode::Relative | ParamSendMode::Hybrid => "/relative",
+            };
+            let address =
+                format!("{}/param/{}{}", address_base, self.param_name(index), suffix);
+            let args = match config.send_mode {
+                ParamSendMode::Absolute if self.params.flag_send_previous.value() => 2,
+                _ => 1,
+            };
+            let min_interval =
+                param_min_interval(config.min_interval_ms, self.params.param_max_rate.value());
+            let rate = match min_interval.as_secs_f32() {
+                0.0 => param_rate,
+                min_interval => param_rate.min(1.0 / min_interval),
+            };
+            //Bundled messages are prefixed with their size
+            let bundled = 4 * self.params.flag_bundle_params.value() as usize;
+            add(rate, osc_message_size(&address, args) + bundled);
+        }
+        if self.params.flag_send_audio.value() && categories.audio {
+            if self.params.audio_mode.value() == AudioMode::Meter {
+                let channels = match self.params.flag_meter_per_channel.value() {
+                    true => MAX_AUDIO_CHANNELS,
+                    false => 1,
+                };
+                //Both in one bundle, each prefixed with its size
+                let peak = format!("{}/audio/peak", address_base);
+                let rms = format!("{}/audio/rms", address_base);
+                let size = osc_message_size(&peak, channels) + osc_message_size(&rms, channels);
+                add(BUFFER_RATE_ESTIMATE, 16 + 8 + size);
+            } else if self.params.flag_audio_blob.value() {
+                let address = format!("{}/audio/block", address_base);
+                //The double timestamp is 4 bytes more than the estimate counts per arg
+                let samples = 4 * AUDIO_BLOCK_FRAMES * MAX_AUDIO_CHANNELS + 4;
+                let blocks = osc_sample_rate / AUDIO_BLOCK_FRAMES as f32;
+                add(blocks, osc_message_size(&address, 3) + samples);
+            } else {
+                let address = format!("{}/audio", address_base);
+                add(osc_sample_rate, osc_message_size(&address, MAX_AUDIO_CHANNELS));
+            }
+            if self.params.flag_send_zcr.value() {
+                let address = format!("{}/audio/zcr", address_base);
+                add(BUFFER_RATE_ESTIMATE, osc_message_size(&address, 1));
+            }
+        }
+        if self.params.flag_send_samples.value() && categories.audio {
+            let address = format!("{}/samples", address_base);
+            //64 bit int, one arg more than the estimate counts with
+            add(BUFFER_RATE_ESTIMATE, osc_message_size(&address, 1) + 4);
+        }
+        if self.params.flag_send_transport.value() {
+            //Only the position changes every buffer, a double like the sample count
+            let address = format!("{}/transport/position", address_base);
+            add(BUFFER_RATE_ESTIMATE, osc_message_size(&address, 1) + 4);
+        }
+        if self.params.flag_heartbeat.value() {
+            let address = format!("{}/heartbeat", address_base);
+            let interval = self.params.heartbeat_interval.value().max(1) as f32;
+            add(1000.0 / interval, osc_message_size(&address, 1));
+        }
+        if self.params.flag_send_onsets.value() && categories.onsets {
+            for band in OnsetBand::ALL {
+                let address = format!("{}/onset/{}", address_base, band.name());
+                add(1.0 / band.refractory_seconds(), osc_message_size(&address, 1));
+            }
+        }
+        //Every extra destination gets a copy of each packet
+        if self.settings.osc_transport == OscTransportMode::Udp {
+            let extra_destinations = self
+                .settings
+                .extra_destinations
+                .iter()
+                .filter(|destination| !destination.trim().is_empty())
+                .count();
+            packets *= (1 + extra_destinations) as f32;
+            bytes *= (1 + extra_destinations) as f32;
+        }
+        format!("{:.0} msg/s, {:.1} kB/s", packets, bytes / 1000.0)
+    }
+
+    fn send_custom(&mut self) {
+        let address = self.settings.custom_address.trim().to_string();
+        if address.is_empty() || address == "/" {
+            self.log.push("Custom message needs an address".to_string());
+            return;
+        }
+        let args = match parse_osc_args(&self.settings.custom_args) {
+            Ok(args) => args,
+            Err(e) => {
+                self.log.push(e);
+                return;
+            }
+        };
+        nih_trace!("Send Custom {} {:?}", address, args);
+        let send_result = self
+            .sender
+            .send(OscChannelMessageType::Custom(OscCustomType {
+                address: address.clone(),
+                args,
+            }));
+        match send_result {
+            Ok(_) => self.log.push(format!("Sent: {} {}", address, self.settings.custom_args)),
+            Err(e) => {
+                nih_error!("Failed to send custom message {:?}", e);
+                self.log.push("Failed to send custom message".to_string());
+            }
+        }
+    }
+
+    /// Goes to the worker like a custom message, the audio thread isn't involved
+    fn send_all_notes_off(&mut self) {
+        let send_result = self
+            .sender
+            .send(OscChannelMessageType::AllNotesOff(OscAllNotesOffType {
+                int_velocity: self.params.flag_midi_int_values.value(),
+            }));
+        match send_result {
+            Ok(_) => self.log.push("Sent all notes off".to_string()),
+            Err(e) => {
+                nih_error!("Failed to send all notes off {:?}", e);
+                self.log.push("Failed to send all notes off".to_string());
+            }
+        }
+    }
+
+    fn export_schema(&mut self) {
+        let path = self.settings.schema_path.trim().to_string();
+        if path.is_empty() {
+            self.log.push("Schema export needs a file path".to_string());
+            return;
+        }
+        let json = match serde_json::to_string_pretty(&schema::build(&self.params)) {
+            Ok(json) => json,
+            Err(e) => {
+                nih_error!("Failed to serialize schema {:?}", e);
+                self.log.push("Failed to build schema".to_string());
+                return;
+            }
+        };
+        match std::fs::write(&path, json) {
+            Ok(_) => self.log.push(format!("Exported schema: {}", path)),
+            Err(e) => {
+                nih_error!("Failed to write schema {:?}", e);
+                self.log.push(format!("Failed to write schema: {}", e));
+            }
+        }
+    }
+
+    fn copy_address(&mut self, cx: &mut EventContext, address: String) {
+        nih_trace!("Copy Address {}", address);
+        match cx.set_clipboard(address.clone()) {
+            Ok(_) => self.log.push(format!("Copied: {}", address)),
+            Err(e) => {
+                nih_error!("Failed to copy address to clipboard {:?}", e);
+                self.log.push("Failed to copy address".to_string());
+            }
+        }
+    }
+
+    /// Reset through the host so it records the change. Params that change are sent by the
+    /// audio thread like any other change, the ones already at their default are sent from here
+    /// so the receiver is in sync with them too
+    fn reset_params(&mut self) {
+        nih_trace!("Reset Params");
+        let setter = ParamSetter::new(self.gui_context.as_ref());
+        let normalized = self.params.flag_normalized_params.value();
+        for index in 0..NUM_PARAMS {
+            let param = &self.params[index];
+            let default = param.default_plain_value();
+            let unchanged = param.value() == default;
+            setter.begin_set_parameter(param);
+            setter.set_parameter(param, default);
+            setter.end_set_parameter(param);
+            //MIDI CCs only go out for the params the host actually changed, the digest picks
+            //up the defaults on the next buffer
+            let config = self.settings.param_configs[index];
+            if !unchanged || !config.route.osc || self.params.flag_param_digest.value() {
+                continue;
+            }
+            let format = self.params.param_value_format.value();
+            let value = osc_param_value(param, default, normalized);
+            let send_result = self.sender.send(OscChannelMessageType::Param(OscParamType {
+                index,
+                value: format.output_value(value, config.bipolar),
+                mode: config.send_mode,
+                comparator: config.comparator,
+                format,
+                slugify_name: self.params.flag_slugify_names.value(),
+                with_previous: self.params.flag_send_previous.value(),
+            }));
+            match send_result {
+                Ok(_) => self.param_activity.mark(index),
+                Err(e) => nih_error!("Failed to send reset param {:?}", e),
+            }
+        }
+        self.log.push("Params reset to defaults".to_string());
+    }
+
+    /// The name param `index` is sent under as it goes into its address
+    fn param_name(&self, index: usize) -> String {
+        param_address_name(
+            param_osc_name(&self.settings.param_addresses[index], &self.params[index]),
+            self.params.flag_slugify_names.value(),
+        )
+    }
+
+    /// Logs where the param now sends to, and warns when another param sends there too
+    fn check_param_address(&mut self, index: usize) {
+        let name = self.param_name(index);
+        let address = format!("{}/param/{}", self.address_base(), name);
+        for other in (0..NUM_PARAMS).filter(|&other| other != index) {
+            if self.param_name(other) == name {
+                self.log.push(format!(
+                    "Warning: param{} and param{} both send to {}",
+                    other + 1,
+                    index + 1,
+                    address
+                ));
+            }
+        }
+        self.log.push(format!("param{} sends to {}", index + 1, address));
+    }
+
+    /// The name param `index` is received under as it goes into its address
+    fn param_receive_name(&self, index: usize) -> String {
+        param_address_name(
+            param_receive_osc_name(
+                &self.settings.param_receive_addresses[index],
+                &self.settings.param_addresses[index],
+                &self.params[index],
+            ),
+            self.params.flag_slugify_names.value(),
+        )
+    }
+
+    /// Logs where the param is now received, and warns when another param is received there too
+    fn check_param_receive_address(&mut self, index: usize) {
+        let name = self.param_receive_name(index);
+        let address = format!("{}/param/{}", self.address_base(), name);
+        for other in (0..NUM_PARAMS).filter(|&other| other != index) {
+            if self.param_receive_name(other) == name {
+                self.log.push(format!(
+                    "Warning: param{} and param{} are both received on {}",
+                    other + 1,
+                    index + 1,
+                    address
+                ));
+            }
+        }
+        self.log.push(format!("param{} receives on {}", index + 1, address));
+    }
+
+    /// Set like the host would, as one gesture per value. The first param with the name gets it
+    fn apply_incoming_params(&self) {
+        let setter = ParamSetter::new(self.gui_context.as_ref());
+        for message in self.incoming.try_iter() {
+            if let OscChannelMessageType::IncomingParam(message) = message {
+                let Some(index) =
+                    (0..NUM_PARAMS).find(|&index| self.param_receive_name(index) == message.name)
+                else {
+                    nih_trace!("No param is received on {}", message.name);
+                    continue;
+                };
+                let param = &self.params[index];
+                setter.begin_set_parameter(param);
+                setter.set_parameter(param, message.value);
+                setter.end_set_parameter(param);
+            }
+        }
+    }
+
+    fn show_received(&mut self) {
+        for message in self.received_source.try_iter() {
+            if let OscChannelMessageType::Received(message) = message {
+                self.received.push(format!(
+                    "{} {} {}",
+                    message.source, message.address, message.args
+                ));
+            }
+        }
+        if self.received.len() > RECEIVED_SHOWN {
+            self.received.drain(..self.received.len() - RECEIVED_SHOWN);
+        }
+    }
+
+    /// The host loaded a preset, whatever was being edited is replaced by what it contains
+    fn reload_settings(&mut self) {
+        let params = &self.params;
+        self.settings.osc_server_address = params.osc_server_address.read().to_string();
+        self.settings.osc_server_port = *params.osc_server_port.read();
+        self.settings.osc_server_port_text = self.settings.osc_server_port.to_string();
+        self.settings.port_error = None;
+        self.settings.osc_recv_port = *params.osc_recv_port.read();
+        self.settings.extra_destinations = params.osc_extra_destinations.read().clone();
+        self.settings.osc_bind_address = params.osc_bind_address.read().to_string();
+        self.settings.osc_bind_port = *params.osc_bind_port.read();
+        self.settings.osc_baud_rate = *params.osc_baud_rate.read();
+        self.settings.osc_address_base = params.osc_address_base.read().to_string();
+        self.settings.osc_transport = *params.osc_transport.read();
+        self.settings.param_configs = *params.param_configs.read();
+        self.settings.param_addresses = params.param_addresses.read().clone();
+        self.settings.param_receive_addresses = params.param_receive_addresses.read().clone();
+        self.settings.named_ccs = *params.named_ccs.read();
+        self.settings.osc_categories = *params.osc_categories.read();
+        self.pending_connection_change = None;
+        self.validate_destination();
+        self.log.push("Settings loaded from preset".to_string());
+    }
+
+    /// WebSockets listen on all interfaces, only the port matters there. The file and serial
+    /// transports take a path in place of the server address
+    fn validate_destination(&mut self) {
+        self.settings.destination_error = match self.settings.osc_transport {
+            OscTransportMode::Udp | OscTransportMode::Tcp => transport::check_destination(
+                &self.settings.osc_server_address,
+                self.settings.osc_server_port,
+            )
+            .err(),
+            OscTransportMode::WebSocket => None,
+            OscTransportMode::File => {
+                transport::validate_dump_path(&self.settings.osc_server_address).err()
+            }
+            OscTransportMode::Serial => {
+                transport::validate_serial_path(&self.settings.osc_server_address).err()
+            }
+        };
+    }
+
+    /// The filled in extra destinations, the ones that aren't written right are logged. They are
+    /// kept anyway, the worker skips what it can't resolve
+    fn extra_destinations(&mut self) -> Vec<String> {
+        let destinations: Vec<String> = self
+            .settings
+            .extra_destinations
+            .iter()
+            .map(|destination| destination.trim().to_string())
+            .filter(|destination| !destination.is_empty())
+            .collect();
+        for destination in &destinations {
+            match transport::check_extra_destination(destination) {
+                Ok(()) => self.log.push(format!("Also sending to {}", destination)),
+                Err(e) => self.log.push(format!("Not sending to {}: {}", destination, e)),
+            }
+        }
+        destinations
+    }
+
+    fn apply_connection_change(&mut self) {
+        self.pending_connection_change = None;
+        if self.settings.osc_transport.has_destination() {
+            //Host names are resolved by the worker, a failed lookup shows up as a connect error
+            if let Err(e) = transport::check_destination(
+                &self.settings.osc_server_address,
+                self.settings.osc_server_port,
+            ) {
+                self.log.push(format!("Not connecting: {}", e));
+                return;
+            }
+            if let Ok(ip) = self.settings.osc_server_address.trim().parse::<IpAddr>() {
+                self.log.push(format!("{} is a {} address", ip, transport::destination_kind(&ip)));
+            }
+        }
+        if self.settings.osc_transport == OscTransportMode::File {
+            if let Err(e) = transport::validate_dump_path(&self.settings.osc_server_address) {
+                self.log.push(format!("Not recording: {}", e));
+                return;
+            }
+        }
+        if self.settings.osc_transport == OscTransportMode::Serial {
+            if let Err(e) = transport::validate_serial_path(&self.settings.osc_server_address) {
+                self.log.push(format!("Not connecting: {}", e));
+                return;
+            }
+        }
+        if self.settings.osc_transport == OscTransportMode::Udp {
+            if let Err(e) = transport::validate_bind_address(&self.settings.osc_bind_address) {
+                self.log.push(format!("Not connecting: {}", e));
+                return;
+            }
+        }
+        nih_trace!(
+            "Connection Changed {}:{} over {}",
+            self.settings.osc_server_address,
+            self.settings.osc_server_port,
+            self.settings.osc_transport
+        );
+        self.log.push(format!("Connecting to: {}:{} over {}", self.settings.osc_server_address, self.settings.osc_server_port, self.settings.osc_transport));
+        let connection = OscConnectionType {
+            transport: self.settings.osc_transport,
+            ip: self.settings.osc_server_address.clone(),
+            port: self.settings.osc_server_port,
+            handshake: self.params.flag_hello_handshake.value(),
+            ttl: self.params.osc_ttl.value() as u32,
+            baud_rate: self.settings.osc_baud_rate,
+            resend: self.params.reconnect_resend.value(),
+            recv_port: self.settings.osc_recv_port,
+            extra_destinations: self.extra_destinations(),
+            bind_address: self.settings.osc_bind_address.trim().to_string(),
+            bind_port: self.settings.osc_bind_port,
+        };
+        let send_result = self.params.commit_connection(connection, &self.sender);
+        if send_result.is_err() {
+            nih_error!("Failed to send ConnectionChange update {:?}", send_result.unwrap_err());
+            self.log.push(format!("Failed change connection"));
+        }
+    }
+}
+
+/// Comma separated args, each either typed explicitly as `i:1`, `f:1`, `s:text` or `b:true`
+/// or guessed as int, float, bool and finally string
+fn parse_osc_args(raw_args: &str) -> Result<Vec<OscType>, String> {
+    let mut args = Vec::new();
+    for raw_arg in raw_args.split(',') {
+        let arg = raw_arg.trim();
+        if arg.is_empty() {
+            continue;
+        }
+        let invalid = |tag: &str| format!("Invalid {} arg: {}", tag, arg);
+        let parsed = match arg.split_once(':') {
+            Some(("i", value)) => OscType::Int(value.trim().parse().map_err(|_| invalid("int"))?),
+            Some(("f", value)) => {
+                OscType::Float(value.trim().parse().map_err(|_| invalid("float"))?)
+            }
+            Some(("b", value)) => OscType::Bool(value.trim().parse().map_err(|_| invalid("bool"))?),
+            Some(("s", value)) => OscType::String(value.to_string()),
+            _ => {
+                if let Ok(value) = arg.parse::<i32>() {
+                    OscType::Int(value)
+                } else if let Ok(value) = arg.parse::<f32>() {
+                    OscType::Float(value)
+                } else if let Ok(value) = arg.parse::<bool>() {
+                    OscType::Bool(value)
+                } else {
+                    OscType::String(arg.trim_matches('"').to_string())
+                }
+            }
+        };
+        args.push(parsed);
+    }
+    Ok(args)
+}
+
+/// Encoded size of an OSC message with 4 byte arguments, strings are null terminated and
+/// padded to 4 bytes
+fn osc_message_size(address: &str, args: usize) -> usize {
+    let padded = |len: usize| (len + 4) & !3;
+    padded(address.len()) + padded(1 + args) + 4 * args
+}
+
 // Makes sense to also define this here, makes it a bit easier to keep track of
 pub(crate) fn default_state() -> Arc<ViziaState> {
-    ViziaState::new(|| (700, 400))
+    ViziaState::new(|| (WINDOW_WIDTH, window_height()))
+}
+
+/// Tall enough for the view with the most rows, so rows added to a view aren't cut off
+fn window_height() -> u32 {
+    let settings = SettingsView::ROWS * ROW_HEIGHT + SettingsView::LOG_HEIGHT as u32;
+    let received = ReceivedView::ROWS * ROW_HEIGHT + ReceivedView::LIST_HEIGHT as u32;
+    let views = [
+        settings,
+        ParamView::ROWS * ROW_HEIGHT,
+        AudioView::ROWS * ROW_HEIGHT,
+        ArpView::ROWS * ROW_HEIGHT + received,
+    ];
+    let content = views.into_iter().max().unwrap_or_default();
+    (TITLE_HEIGHT + content).min(MAX_WINDOW_HEIGHT)
 }
 
 pub(crate) fn create(
     params: Arc<OsClapParams>,
-    sender: Arc<Sender<OscChannelMessageType>>,
+    channels: EditorChannels,
+    handshake_state: Arc<RwLock<HandshakeState>>,
+    connection_status: Arc<RwLock<ConnectionStatus>>,
+    param_activity: Arc<ParamActivity>,
+    process_timing: Arc<ProcessTiming>,
     editor_state: Arc<ViziaState>,
 ) -> Option<Box<dyn Editor>> {
-    create_vizia_editor(editor_state, ViziaTheming::Custom, move |cx, _| {
+    create_vizia_editor(editor_state, ViziaTheming::Custom, move |cx, gui_context| {
         assets::register_noto_sans_light(cx);
         assets::register_noto_sans_thin(cx);
 
-        OsClapEditor {
-            sender: sender.clone(),
+        //Whatever came in while the editor was closed is stale by now
+        channels.incoming.try_iter().for_each(drop);
+        channels.received.try_iter().for_each(drop);
+
+        let mut editor = OsClapEditor {
+            sender: channels.sender.clone(),
+            incoming: channels.incoming.clone(),
+            received_source: channels.received.clone(),
             params: params.clone(),
+            gui_context,
             log: Vec::new(),
+            received: Vec::new(),
             settings: OscSettings {
                 osc_server_address: params.osc_server_address.read().to_string(),
                 osc_server_port: *params.osc_server_port.read(),
-                osc_address_base: params.osc_address_base.read().to_string()
-            }.into()
-        }
-        .build(cx);
+                osc_server_port_text: params.osc_server_port.read().to_string(),
+                port_error: None,
+                osc_recv_port: *params.osc_recv_port.read(),
+                extra_destinations: params.osc_extra_destinations.read().clone(),
+                osc_bind_address: params.osc_bind_address.read().to_string(),
+                osc_bind_port: *params.osc_bind_port.read(),
+                osc_baud_rate: *params.osc_baud_rate.read(),
+                osc_address_base: params.osc_address_base.read().to_string(),
+                osc_transport: *params.osc_transport.read(),
+                param_configs: *params.param_configs.read(),
+                param_addresses: params.param_addresses.read().clone(),
+                param_receive_addresses: params.param_receive_addresses.read().clone(),
+                named_ccs: *params.named_ccs.read(),
+                osc_categories: *params.osc_categories.read(),
+                custom_address: String::new(),
+                custom_args: String::new(),
+                schema_path: String::new(),
+                destination_error: None,
+            }.into(),
+            pending_connection_change: None,
+            reset_armed: None,
+            bandwidth_estimate: String::new(),
+            last_bandwidth_estimate: None,
+            handshake_state: handshake_state.clone(),
+            receiver_status: String::new(),
+            connection_status_source: connection_status.clone(),
+            connection_status: ConnectionStatus::Disconnected,
+            param_activity: param_activity.clone(),
+            param_active: [false; NUM_PARAMS],
+            process_timing: process_timing.clone(),
+            process_time: String::new(),
+            last_process_timing: Instant::now(),
+            dropped: channels.dropped.clone(),
+            last_dropped_check: Instant::now(),
+            last_send_totals: channels.send_stats.totals(),
+            send_stats: channels.send_stats.clone(),
+            last_send_rate_check: Instant::now(),
+            send_rate: String::new(),
+            settings_loads: params.settings_loads.load(Ordering::Acquire),
+        };
+        editor.validate_destination();
+        editor.build(cx);
+
+        //Drive time based editor work, stops once the window is gone
+        cx.spawn(|cx_proxy| loop {
+            thread::sleep(EDITOR_TICK_INTERVAL);
+            if cx_proxy.emit(OsClapEditorEvent::Tick).is_err() {
+                break;
+            }
+        });
 
         //Uncomment for debugging styles
         //cx.add_stylesheet(include_style!("src/style.css")).expect("Failed to load stylesheet");
@@ -123,9 +1162,35 @@ pub(crate) fn create(
                 .font_size(40.0 * POINT_SCALE)
                 .left(Units::Pixels(5.0))
                 .class("title");
-            HStack::new(cx, |cx| {
-                SettingsView::new(cx, OsClapEditor::settings, OsClapEditor::params, OsClapEditor::log);
-                ParamView::new(cx, OsClapEditor::params);
+            ScrollView::new(cx, 0.0, 0.0, false, true, |cx| {
+                HStack::new(cx, |cx| {
+                    SettingsView::new(
+                        cx,
+                        OsClapEditor::settings,
+                        OsClapEditor::params,
+                        OsClapEditor::log,
+                        OsClapEditor::bandwidth_estimate,
+                        OsClapEditor::receiver_status,
+                        OsClapEditor::connection_status,
+                        OsClapEditor::process_time,
+                        OsClapEditor::send_rate,
+                    );
+                    ParamView::new(
+                        cx,
+                        OsClapEditor::params,
+                        OsClapEditor::settings,
+                        OsClapEditor::reset_armed.map(|armed| armed.is_some()),
+                        OsClapEditor::param_active,
+                    );
+                    AudioView::new(cx, OsClapEditor::params);
+                    //Under the arp, which leaves most of its column free
+                    VStack::new(cx, |cx| {
+                        ArpView::new(cx, OsClapEditor::params);
+                        ReceivedView::new(cx, OsClapEditor::received);
+                    });
+                })
+                //Sized by the rows so the scroll view knows how far to scroll
+                .height(Auto);
             });
         });
     })
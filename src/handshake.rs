@@ -0,0 +1,141 @@
+use nih_plug::debug::*;
+use parking_lot::RwLock;
+use rosc::{OscMessage, OscPacket, OscType};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::transport::OscTransport;
+
+/// How long the receiver gets to answer the hello before we stop waiting
+const HELLO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Big enough for any hello_ack a receiver could reasonably send
+const INBOUND_BUFFER_SIZE: usize = 1536;
+
+/// What the receiver said it can handle in its hello_ack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub features: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn bundles(&self) -> bool {
+        self.features.iter().any(|feature| feature == "bundles")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandshakeState {
+    Disabled,
+    Waiting(Instant),
+    Acknowledged(Capabilities),
+    NoReply,
+}
+
+impl HandshakeState {
+    pub fn is_waiting(&self) -> bool {
+        matches!(self, HandshakeState::Waiting(_))
+    }
+
+    /// Everything is assumed supported unless the receiver told us otherwise
+    pub fn supports_bundles(&self) -> bool {
+        match self {
+            HandshakeState::Acknowledged(capabilities) => capabilities.bundles(),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for HandshakeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandshakeState::Disabled => write!(f, "Off"),
+            HandshakeState::Waiting(_) => write!(f, "Waiting for reply"),
+            HandshakeState::Acknowledged(capabilities) if capabilities.features.is_empty() => {
+                write!(f, "No features")
+            }
+            HandshakeState::Acknowledged(capabilities) => {
+                write!(f, "{}", capabilities.features.join(", "))
+            }
+            HandshakeState::NoReply => write!(f, "No reply"),
+        }
+    }
+}
+
+/// Send the hello if the handshake is enabled, called whenever the worker connects
+pub fn start(
+    transport: &mut dyn OscTransport,
+    address_base: &str,
+    enabled: bool,
+    state: &RwLock<HandshakeState>,
+) {
+    transport.set_receiving(enabled);
+    if !enabled {
+        *state.write() = HandshakeState::Disabled;
+        return;
+    }
+    let hello = OscPacket::Message(OscMessage {
+        addr: format!("{}/hello", address_base),
+        args: vec![OscType::String(env!("CARGO_PKG_VERSION").to_string())],
+    });
+    match rosc::encoder::encode(&hello) {
+        Ok(buf) => {
+            if let Err(e) = transport.send(&buf) {
+                nih_error!("Failed to send hello {:?}", e);
+            }
+        }
+        Err(e) => nih_error!("Failed to encode hello {:?}", e),
+    }
+    *state.write() = HandshakeState::Waiting(Instant::now());
+}
+
+/// Check for the hello_ack, anything else the receiver sends is ignored
+pub fn poll(transport: &mut dyn OscTransport, address_base: &str, state: &RwLock<HandshakeState>) {
+    let since = match *state.read() {
+        HandshakeState::Waiting(since) => since,
+        _ => return,
+    };
+    let mut buf = [0u8; INBOUND_BUFFER_SIZE];
+    loop {
+        match transport.try_recv(&mut buf) {
+            Ok(Some(len)) => {
+                if let Some(capabilities) = parse_ack(address_base, &buf[..len]) {
+                    nih_trace!("Receiver capabilities: {:?}", capabilities);
+                    transport.set_receiving(false);
+                    *state.write() = HandshakeState::Acknowledged(capabilities);
+                    return;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                nih_error!("Failed to read from receiver {:?}", e);
+                break;
+            }
+        }
+    }
+    if since.elapsed() >= HELLO_TIMEOUT {
+        nih_trace!("No hello_ack from receiver");
+        transport.set_receiving(false);
+        *state.write() = HandshakeState::NoReply;
+    }
+}
+
+/// `/<base>/hello_ack <feature>...` with every supported feature as a string arg
+fn parse_ack(address_base: &str, buf: &[u8]) -> Option<Capabilities> {
+    let message = match rosc::decoder::decode_udp(buf) {
+        Ok((_, OscPacket::Message(message))) => message,
+        _ => return None,
+    };
+    if message.addr != format!("{}/hello_ack", address_base) {
+        return None;
+    }
+    let features = message
+        .args
+        .into_iter()
+        .filter_map(|arg| match arg {
+            OscType::String(feature) => Some(feature),
+            _ => None,
+        })
+        .collect();
+    Some(Capabilities { features })
+}
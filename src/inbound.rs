@@ -0,0 +1,210 @@
+use anyhow::Result;
+use nih_plug::debug::*;
+use rosc::{OscMessage, OscPacket, OscType};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+
+/// Param messages are a few dozen bytes, anything bigger isn't meant for us
+const INBOUND_BUFFER_SIZE: usize = 1536;
+
+/// Listens on its own port for a controller setting params, `/<base>/param/<name> <value>`
+pub struct ParamReceiver {
+    socket: UdpSocket,
+    port: u16,
+}
+
+impl ParamReceiver {
+    /// All interfaces, so controllers on other machines reach us too
+    pub fn bind(port: u16) -> Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, port })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Reads everything waiting, `apply` gets the param name and value of every param message.
+    /// `received` gets every message with where it came from, params or not
+    pub fn poll(
+        &self,
+        address_base: &str,
+        mut apply: impl FnMut(&str, f32),
+        mut received: impl FnMut(SocketAddr, &OscMessage),
+    ) {
+        let mut buf = [0u8; INBOUND_BUFFER_SIZE];
+        loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, source)) => match rosc::decoder::decode_udp(&buf[..len]) {
+                    Ok((_, packet)) => {
+                        let mut received = |message: &OscMessage| received(source, message);
+                        apply_packet(address_base, packet, &mut apply, &mut received)
+                    }
+                    Err(e) => nih_trace!("Ignoring inbound packet that isn't OSC {:?}", e),
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    nih_error!("Failed to read inbound params {:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Bundles are unpacked in order, their timetags are ignored
+fn apply_packet(
+    address_base: &str,
+    packet: OscPacket,
+    apply: &mut impl FnMut(&str, f32),
+    received: &mut impl FnMut(&OscMessage),
+) {
+    match packet {
+        OscPacket::Message(message) => {
+            received(&message);
+            match parse_param(address_base, &message) {
+                Some((name, value)) => apply(name, value),
+                None => nih_trace!("Ignoring inbound {}", message.addr),
+            }
+        }
+        OscPacket::Bundle(bundle) => {
+            for packet in bundle.content {
+                apply_packet(address_base, packet, apply, received);
+            }
+        }
+    }
+}
+
+/// The value is clamped to the 0-1 range of the params, which param has the name is up to the
+/// editor since it can be renamed
+fn parse_param<'a>(address_base: &str, message: &'a OscMessage) -> Option<(&'a str, f32)> {
+    let name = message.addr.strip_prefix(address_base)?.strip_prefix("/param/")?;
+    if name.is_empty() || name.contains('/') {
+        return None;
+    }
+    let value = match message.args.first()? {
+        OscType::Float(value) => *value,
+        OscType::Double(value) => *value as f32,
+        _ => return None,
+    };
+    if value.is_nan() {
+        return None;
+    }
+    Some((name, value.clamp(0.0, 1.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosc::{OscBundle, OscTime};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn message(addr: &str, args: Vec<OscType>) -> OscMessage {
+        OscMessage {
+            addr: addr.to_string(),
+            args,
+        }
+    }
+
+    fn parse(addr: &str, args: Vec<OscType>) -> Option<(String, f32)> {
+        let message = message(addr, args);
+        parse_param("/base", &message).map(|(name, value)| (name.to_string(), value))
+    }
+
+    /// Params and addresses of every message, in the order they were handed out
+    fn apply(packet: OscPacket) -> (Vec<(String, f32)>, Vec<String>) {
+        let mut params = Vec::new();
+        let mut received = Vec::new();
+        apply_packet(
+            "/base",
+            packet,
+            &mut |name, value| params.push((name.to_string(), value)),
+            &mut |message| received.push(message.addr.clone()),
+        );
+        (params, received)
+    }
+
+    #[test]
+    fn parses_param_values() {
+        let value = parse("/base/param/cutoff", vec![OscType::Float(0.25)]);
+        assert_eq!(value, Some(("cutoff".to_string(), 0.25)));
+        let value = parse("/base/param/cutoff", vec![OscType::Double(0.5)]);
+        assert_eq!(value, Some(("cutoff".to_string(), 0.5)));
+    }
+
+    #[test]
+    fn ignores_unknown_addresses() {
+        let value = || vec![OscType::Float(0.5)];
+        assert_eq!(parse("/other/param/cutoff", value()), None);
+        assert_eq!(parse("/base/note_on", value()), None);
+        assert_eq!(parse("/base/param/", value()), None);
+        assert_eq!(parse("/base/param/cutoff/relative", value()), None);
+        assert_eq!(parse("/base/paramcutoff", value()), None);
+    }
+
+    #[test]
+    fn clamps_values_and_ignores_the_rest() {
+        let parse = |arg| parse("/base/param/cutoff", vec![arg]).map(|(_, value)| value);
+        assert_eq!(parse(OscType::Float(1.5)), Some(1.0));
+        assert_eq!(parse(OscType::Float(-0.5)), Some(0.0));
+        assert_eq!(parse(OscType::Double(f64::INFINITY)), Some(1.0));
+        assert_eq!(parse(OscType::Float(f32::NAN)), None);
+        assert_eq!(parse(OscType::Int(1)), None);
+        assert_eq!(parse(OscType::String("0.5".to_string())), None);
+        assert_eq!(parse_param("/base", &message("/base/param/cutoff", Vec::new())), None);
+    }
+
+    #[test]
+    fn unpacks_nested_bundles_in_order() {
+        let bundle = |content| {
+            OscPacket::Bundle(OscBundle {
+                timetag: OscTime {
+                    seconds: 0,
+                    fractional: 1,
+                },
+                content,
+            })
+        };
+        let param = |name: &str, value| {
+            let addr = format!("/base/param/{}", name);
+            OscPacket::Message(message(&addr, vec![OscType::Float(value)]))
+        };
+        let packet = bundle(vec![
+            param("a", 0.1),
+            bundle(vec![param("b", 0.2), OscPacket::Message(message("/base/other", Vec::new()))]),
+            param("c", 0.3),
+        ]);
+        let (params, received) = apply(packet);
+        let names: Vec<&str> = params.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(
+            received,
+            ["/base/param/a", "/base/param/b", "/base/other", "/base/param/c"]
+        );
+    }
+
+    #[test]
+    fn polls_values_with_their_source() {
+        let param_receiver = ParamReceiver::bind(0).unwrap();
+        let port = param_receiver.socket.local_addr().unwrap().port();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let packet = OscPacket::Message(message("/base/param/cutoff", vec![OscType::Float(0.75)]));
+        sender.send_to(&rosc::encoder::encode(&packet).unwrap(), ("127.0.0.1", port)).unwrap();
+
+        let mut params = Vec::new();
+        let mut sources = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while params.is_empty() && Instant::now() < deadline {
+            param_receiver.poll(
+                "/base",
+                |name, value| params.push((name.to_string(), value)),
+                |source, _| sources.push(source),
+            );
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(params, [("cutoff".to_string(), 0.75)]);
+        assert_eq!(sources, [sender.local_addr().unwrap()]);
+    }
+}
@@ -1,79 +1,172 @@
+use analysis::{
+    AnalysisChannel, LevelFollower, LevelMapping, OnsetBand, OnsetDetector, OnsetSettings,
+};
 use anyhow::Result;
-use crossbeam_channel::{Receiver, Sender};
+use arpeggiator::{ArpNote, ArpPattern, Arpeggiator};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TrySendError};
+use handshake::HandshakeState;
+use inbound::ParamReceiver;
 use nih_plug::debug::*;
+use nih_plug::formatters;
+use nih_plug::params::persist::PersistentField;
 use nih_plug::prelude::*;
 use nih_plug_vizia::ViziaState;
-use parking_lot::RwLock;
-use rosc::{OscMessage, OscPacket, OscType};
+use parking_lot::{Mutex, RwLock};
+use rosc::{OscBundle, OscMessage, OscPacket, OscTime, OscType};
 use rubato::{FftFixedOut, Resampler};
-use std::net::UdpSocket;
-use std::sync::atomic::AtomicBool;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::thread;
 use std::thread::JoinHandle;
-use std::ops::Index;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::ops::{Deref, Index};
+use thread_priority::ThreadPriority;
+use transport::{ObservedTransport, OscTransport, OscTransportMode, PacketObserver, SendStats};
+use voices::{Voice, Voices};
 
+mod analysis;
+mod arpeggiator;
 mod editor;
+mod handshake;
+mod inbound;
+mod schema;
 mod subviews;
+mod transport;
+mod voices;
 
 pub struct OsClap {
     params: Arc<OsClapParams>,
     osc_thread: Option<JoinHandle<()>>,
+    /// Stops the current worker when its Exit can't be queued, every worker gets a new one
+    osc_thread_stop: Arc<AtomicBool>,
+    /// Nothing is sent on it, it disconnects once the current worker has exited
+    osc_thread_done: Receiver<()>,
     sender: Arc<Sender<OscChannelMessageType>>,
-    receiver: Option<Receiver<OscChannelMessageType>>,
+    /// The same channel for what the audio thread streams, see `RealtimeSender`
+    realtime_sender: RealtimeSender,
+    audio_blocks: AudioBlockPool,
+    /// Kept for the lifetime of the plugin, every worker gets a clone so a restarted worker
+    /// picks up the same channel the editor is sending to
+    receiver: Receiver<OscChannelMessageType>,
+    /// Param values the worker received, applied by the editor
+    incoming: OscChannel,
+    /// Every message the worker received, shown in the editor
+    received: OscChannel,
     editor_state: Arc<ViziaState>,
+    /// Written by the worker, shown in the editor
+    handshake_state: Arc<RwLock<HandshakeState>>,
+    /// Shared with every worker, see `set_packet_observer`
+    packet_observer: PacketObserver,
+    /// Counted by every worker, shown in the editor
+    send_stats: Arc<SendStats>,
+    /// Written by initialize and the worker, shown in the editor
+    connection_status: Arc<RwLock<ConnectionStatus>>,
+    /// Written on every param send, shown in the editor
+    param_activity: Arc<ParamActivity>,
+    process_timing: Arc<ProcessTiming>,
     input_sample_rate: f32,
-    resampler: Option<FftFixedOut<f32>>,
-    resampler_buffer: Option<Vec<Vec<f32>>>,
-    p1_dirty: Arc<AtomicBool>,
-    p2_dirty: Arc<AtomicBool>,
-    p3_dirty: Arc<AtomicBool>,
-    p4_dirty: Arc<AtomicBool>,
-    p5_dirty: Arc<AtomicBool>,
-    p6_dirty: Arc<AtomicBool>,
-    p7_dirty: Arc<AtomicBool>,
-    p8_dirty: Arc<AtomicBool>,
+    /// `None` while audio processing is disabled
+    resampler: Option<AudioResampler>,
+    /// Where the background thread leaves resamplers built for a new OSC sample rate
+    resampler_swap: Arc<Mutex<ResamplerSwap>>,
+    /// Samples since initialize before the first one in the resampler's input
+    resampler_input_start: u64,
+    max_buffer_size: usize,
+    /// Channels of the host's input, the resampler gets as many
+    input_channels: usize,
+    onset_detector: Option<OnsetDetector>,
+    level_follower: LevelFollower,
+    /// Last value sent per param in sample accurate mode, only changes are sent
+    sent_param_values: [f32; NUM_PARAMS],
+    /// Latches of the params in toggle mode
+    param_toggles: [ParamToggle; NUM_PARAMS],
+    /// Samples left in which param changes are swallowed after initialize
+    load_grace_samples: usize,
+    /// Ticks of the param clock
+    param_clock: BeatClock,
+    arp_clock: BeatClock,
+    arpeggiator: Arpeggiator,
+    /// Notes held by the host, for params sent as note expressions
+    voices: Voices,
+    /// Last value sent per param as a note expression
+    expression_values: [f32; NUM_PARAMS],
+    /// What the worker was last told about repeat compression, `None` for a new worker
+    compress_repeats: Option<bool>,
+    /// Samples processed since initialize before the current buffer
+    sample_count: u64,
+    /// Host transport as of the last transport message
+    sent_transport: OscTransportType,
+    /// `OsClapParams::settings_loads` the worker has the settings of
+    applied_settings_loads: u32,
+    /// Realtime copy of the persisted param configs, refreshed whenever the lock is free
+    param_configs: [ParamConfig; NUM_PARAMS],
+    /// `flag_normalized_params` as of the start of the buffer, so every param in it is sent
+    /// the same way
+    normalized_params: bool,
+    /// Realtime copy of the persisted named CCs
+    named_ccs: [NamedCc; 3],
+    /// Set by the param callbacks, taken when the change is sent
+    param_dirty: Arc<[AtomicBool; NUM_PARAMS]>,
 }
 
 impl Default for OsClap {
     fn default() -> Self {
-        let p1_dirty = Arc::new(AtomicBool::new(false));
-        let p2_dirty = Arc::new(AtomicBool::new(false));
-        let p3_dirty = Arc::new(AtomicBool::new(false));
-        let p4_dirty = Arc::new(AtomicBool::new(false));
-        let p5_dirty = Arc::new(AtomicBool::new(false));
-        let p6_dirty = Arc::new(AtomicBool::new(false));
-        let p7_dirty = Arc::new(AtomicBool::new(false));
-        let p8_dirty = Arc::new(AtomicBool::new(false));
+        let param_dirty = Arc::new(std::array::from_fn(|_| AtomicBool::new(false)));
 
         let channel = OscChannel::default();
+        let sender = Arc::new(channel.sender);
         Self {
-            params: Arc::new(OsClapParams::new(
-                p1_dirty.clone(),
-                p2_dirty.clone(),
-                p3_dirty.clone(),
-                p4_dirty.clone(),
-                p5_dirty.clone(),
-                p6_dirty.clone(),
-                p7_dirty.clone(),
-                p8_dirty.clone(),
-            )),
+            params: Arc::new(OsClapParams::new(&param_dirty)),
             osc_thread: None,
-            sender: Arc::new(channel.sender),
-            receiver: Some(channel.receiver),
+            osc_thread_stop: Arc::new(AtomicBool::new(false)),
+            //Already disconnected, there is no worker yet
+            osc_thread_done: crossbeam_channel::bounded(0).1,
+            realtime_sender: RealtimeSender {
+                sender: sender.clone(),
+                dropped: Arc::new(AtomicU64::new(0)),
+                enabled: true,
+            },
+            audio_blocks: AudioBlockPool::default(),
+            sender,
+            receiver: channel.receiver,
+            incoming: OscChannel::incoming(),
+            received: OscChannel::received(),
             input_sample_rate: 1.0,
             resampler: None,
-            resampler_buffer: None,
+            resampler_swap: Arc::new(Mutex::new(ResamplerSwap::default())),
+            resampler_input_start: 0,
+            max_buffer_size: 0,
+            input_channels: 2,
+            onset_detector: None,
+            level_follower: LevelFollower::default(),
+            sent_param_values: [f32::NAN; NUM_PARAMS],
+            param_toggles: [ParamToggle::default(); NUM_PARAMS],
+            load_grace_samples: 0,
+            param_clock: BeatClock::default(),
+            arp_clock: BeatClock::default(),
+            arpeggiator: Arpeggiator::default(),
+            voices: Voices::default(),
+            expression_values: [f32::NAN; NUM_PARAMS],
+            compress_repeats: None,
+            sample_count: 0,
+            sent_transport: OscTransportType::default(),
+            applied_settings_loads: 0,
+            param_configs: std::array::from_fn(ParamConfig::new),
+            normalized_params: false,
+            named_ccs: std::array::from_fn(NamedCc::new),
             editor_state: editor::default_state(),
-            p1_dirty,
-            p2_dirty,
-            p3_dirty,
-            p4_dirty,
-            p5_dirty,
-            p6_dirty,
-            p7_dirty,
-            p8_dirty,
+            handshake_state: Arc::new(RwLock::new(HandshakeState::Disabled)),
+            packet_observer: Arc::new(RwLock::new(None)),
+            send_stats: Arc::new(SendStats::new()),
+            connection_status: Arc::new(RwLock::new(ConnectionStatus::Disconnected)),
+            param_activity: Arc::new(ParamActivity::new()),
+            process_timing: Arc::new(ProcessTiming::new()),
+            param_dirty,
         }
     }
 }
@@ -96,531 +189,4086 @@ impl Default for OscChannel {
     }
 }
 
+/// Never blocks the audio thread, a message that doesn't fit in the full channel is dropped and
+/// counted for the editor instead
+struct RealtimeSender {
+    sender: Arc<Sender<OscChannelMessageType>>,
+    dropped: Arc<AtomicU64>,
+    /// Off while networking is disabled, messages are left out instead of filling the channel
+    enabled: bool,
+}
+
+impl RealtimeSender {
+    fn send(&self, message: OscChannelMessageType) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        match self.sender.try_send(message) {
+            Ok(_) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(e @ TrySendError::Disconnected(_)) => Err(e.into()),
+        }
+    }
+}
+
+/// Sample buffers for audio blocks, allocated up front and handed back when a block is dropped
+/// so the audio thread never allocates one. Without a free buffer the block is dropped
+struct AudioBlockPool {
+    sender: Sender<Vec<f32>>,
+    receiver: Receiver<Vec<f32>>,
+}
+
+impl Default for AudioBlockPool {
+    fn default() -> Self {
+        let (sender, receiver) = crossbeam_channel::bounded(AUDIO_BLOCK_POOL_SIZE);
+        for _ in 0..AUDIO_BLOCK_POOL_SIZE {
+            let _ = sender.try_send(Vec::with_capacity(AUDIO_BLOCK_FRAMES * MAX_AUDIO_CHANNELS));
+        }
+        Self { sender, receiver }
+    }
+}
+
+impl OscChannel {
+    /// From the worker to the editor, values past this are dropped while the editor is closed
+    fn incoming() -> Self {
+        let (sender, receiver) = crossbeam_channel::bounded(INCOMING_PARAMS_LIMIT);
+        Self { sender, receiver }
+    }
+
+    /// From the worker to the editor, messages past this are dropped while the editor is closed
+    fn received() -> Self {
+        let (sender, receiver) = crossbeam_channel::bounded(RECEIVED_MESSAGES_LIMIT);
+        Self { sender, receiver }
+    }
+}
+
+/// Params are passed by index, the worker looks up the name they are sent under so the audio
+/// thread doesn't allocate for it
+#[derive(Clone, Copy)]
 struct OscParamType {
-    name: String,
+    index: usize,
+    value: f32,
+    mode: ParamSendMode,
+    comparator: ParamComparator,
+    format: ParamValueFormat,
+    slugify_name: bool,
+    /// Absolute sends carry the previous value as a second arg
+    with_previous: bool,
+}
+
+struct OscParamDigestType {
+    values: [f32; NUM_PARAMS],
+    format: ParamValueFormat,
+    /// Each value is preceded by its param name so the receiver can map them by itself
+    with_names: bool,
+    slugify_name: bool,
+}
+
+struct OscParamExpressionType {
+    index: usize,
+    /// -1 for notes the host gave no voice id
+    voice_id: i32,
+    channel: u8,
+    note: u8,
     value: f32,
+    slugify_name: bool,
+}
+
+/// The params that changed in one process call, sent as a single bundle
+struct OscParamBatchType {
+    /// By param index, a fixed array so batching doesn't allocate on the audio thread
+    params: [Option<OscParamType>; NUM_PARAMS],
+    /// Shifts the timetag of the bundle, see `osc_time`
+    time_offset_ms: i32,
 }
 
 struct OscNoteType {
     channel: u8,
     note: u8,
     velocity: f32,
+    /// Send the velocity as a 0-127 int instead of a float
+    int_velocity: bool,
+    /// Send pitch and velocity as separate messages
+    split: bool,
+    /// Bundle a `/trigger` impulse with the note
+    trigger: bool,
+    /// Shifts the timetag of the split bundle, see `osc_time`
+    time_offset_ms: i32,
+}
+
+impl OscNoteType {
+    /// The float goes out exactly as the host sent it, the int is clamped to 0-127
+    fn velocity_arg(&self) -> OscType {
+        if self.int_velocity {
+            OscType::Int((self.velocity.clamp(0.0, 1.0) * 127.0).round() as i32)
+        } else {
+            OscType::Float(self.velocity)
+        }
+    }
 }
 
 struct OscAudioType {
     value: f32,
 }
 
+/// One resampled sample of every channel
+struct OscAudioFrameType {
+    samples: [f32; MAX_AUDIO_CHANNELS],
+    /// How many of `samples` are used, 1 for mono
+    channels: usize,
+}
+
+/// Peak and RMS of a host buffer
+struct OscAudioMeterType {
+    peak: [f32; MAX_AUDIO_CHANNELS],
+    rms: [f32; MAX_AUDIO_CHANNELS],
+    /// How many of the levels are used, 1 when measured across all channels
+    channels: usize,
+}
+
+/// The host's transport, `None` for what didn't change or what the host doesn't tell
+#[derive(Default, Clone, Copy, PartialEq)]
+struct OscTransportType {
+    tempo: Option<f64>,
+    playing: Option<bool>,
+    /// In quarter notes from the start of the song
+    position: Option<f64>,
+}
+
+struct OscSampleCountType {
+    samples: u64,
+}
+
+struct OscAudioBlockType {
+    /// Seconds since initialize at the first input sample of the block
+    time: f64,
+    channels: usize,
+    /// Interleaved, one frame of every channel after the other
+    samples: Vec<f32>,
+    /// Where `samples` goes back to, see `AudioBlockPool`
+    pool: Sender<Vec<f32>>,
+}
+
+impl Drop for OscAudioBlockType {
+    fn drop(&mut self) {
+        let _ = self.pool.try_send(std::mem::take(&mut self.samples));
+    }
+}
+
+struct OscNamedCcType {
+    name: &'static str,
+    channel: u8,
+    value: f32,
+    format: ParamValueFormat,
+}
+
+/// A CC, pitch bend or aftertouch value
+struct OscMidiValueType {
+    channel: u8,
+    /// The controller of a CC or the note of poly aftertouch
+    number: Option<u8>,
+    value: f32,
+    format: ParamValueFormat,
+}
+
+struct OscParamModulationType {
+    index: usize,
+    voice_id: i32,
+    value: f32,
+    slugify_name: bool,
+}
+
+struct OscOnsetType {
+    band: OnsetBand,
+    strength: f32,
+}
+
 struct OscConnectionType {
+    transport: OscTransportMode,
     ip: String,
     port: u16,
+    /// Say hello after connecting and wait for the receiver to list what it supports
+    handshake: bool,
+    /// Hop limit of UDP packets
+    ttl: u32,
+    /// Speed of the serial device
+    baud_rate: u32,
+    /// Which params go out again once a lost connection is back
+    resend: ReconnectResend,
+    /// Where param values are received, 0 when nothing is received
+    recv_port: u16,
+    /// `host:port` of the destinations that get every packet too, UDP only
+    extra_destinations: Vec<String>,
+    /// Local address and port UDP packets are sent from, port 0 lets the OS pick
+    bind_address: String,
+    bind_port: u16,
 }
 
 struct OscAddressBaseType {
     address: String,
 }
 
+/// What each param is called in its address, blank for the param's own name
+struct OscParamAddressesType {
+    addresses: [String; NUM_PARAMS],
+}
+
+struct OscInfoType {
+    sample_rate: f32,
+    osc_sample_rate: i32,
+}
+
+struct OscIncomingParamType {
+    /// As it was in the address
+    name: String,
+    /// Already clamped to the range of the param
+    value: f32,
+}
+
+/// Any message the worker received, for the editor to show
+struct OscReceivedType {
+    source: SocketAddr,
+    address: String,
+    /// Formatted like in a dump file
+    args: String,
+}
+
+/// A note off for every channel and note, for receivers left with stuck notes
+struct OscAllNotesOffType {
+    /// Velocities as 0-127 ints, like the other note offs
+    int_velocity: bool,
+}
+
+struct OscCustomType {
+    /// Sent as is when it starts with a slash, otherwise under the address base
+    address: String,
+    args: Vec<OscType>,
+}
+
 
 
 enum OscChannelMessageType {
     Exit,
     ConnectionChange(OscConnectionType),
+    Info(OscInfoType),
     AddressBaseChange(OscAddressBaseType),
+    /// Params queued before this go out under the old names, the ones after under the new
+    ParamAddressesChange(OscParamAddressesType),
+    /// Whether a packet identical to the one before goes out as a repeat count
+    RepeatsChange(bool),
     Param(OscParamType),
+    ParamBatch(OscParamBatchType),
+    ParamDigest(OscParamDigestType),
+    ParamModulation(OscParamModulationType),
+    ParamExpression(OscParamExpressionType),
     NoteOn(OscNoteType),
     NoteOff(OscNoteType),
-    Audio(OscAudioType),
+    NamedCc(OscNamedCcType),
+    Cc(OscMidiValueType),
+    PitchBend(OscMidiValueType),
+    Aftertouch(OscMidiValueType),
+    Audio(OscAudioFrameType),
+    AudioMeter(OscAudioMeterType),
+    AudioBlock(OscAudioBlockType),
+    AudioZcr(OscAudioType),
+    SampleCount(OscSampleCountType),
+    Transport(OscTransportType),
+    Onset(OscOnsetType),
+    Custom(OscCustomType),
+    AllNotesOff(OscAllNotesOffType),
+    /// The worker sends the range of every param, see `meta_packet`
+    SendMeta,
+    CategoriesChange(CategoryMask),
+    /// A param value the worker received, it goes from the worker to the editor
+    IncomingParam(OscIncomingParamType),
+    /// Goes from the worker to the editor as well
+    Received(OscReceivedType),
 }
 
-#[derive(Params)]
-pub struct OsClapParams {
-    //Persisted Settings
-    #[persist = "osc_server_address"]
-    osc_server_address: RwLock<String>,
-    #[persist = "osc_server_port"]
-    osc_server_port: RwLock<u16>,
-    #[persist = "osc_address_base"]
-    osc_address_base: RwLock<String>,
-
-    //Setting Flags
-    #[id = "flag_send_midi"]
-    flag_send_midi: BoolParam,
-    #[id = "flag_send_audio"]
-    flag_send_audio: BoolParam,
-    #[id = "osc_sample_rate"]
-    osc_sample_rate: IntParam,
+impl OscChannelMessageType {
+    /// None for messages that steer the worker or are always wanted, like custom messages
+    fn category(&self) -> Option<MessageCategory> {
+        match self {
+            OscChannelMessageType::Param(_)
+            | OscChannelMessageType::ParamBatch(_)
+            | OscChannelMessageType::ParamDigest(_)
+            | OscChannelMessageType::ParamModulation(_)
+            | OscChannelMessageType::ParamExpression(_)
+            | OscChannelMessageType::SendMeta => Some(MessageCategory::Params),
+            OscChannelMessageType::NoteOn(_)
+            | OscChannelMessageType::NoteOff(_)
+            | OscChannelMessageType::NamedCc(_)
+            | OscChannelMessageType::Cc(_)
+            | OscChannelMessageType::PitchBend(_)
+            | OscChannelMessageType::Aftertouch(_)
+            | OscChannelMessageType::AllNotesOff(_) => Some(MessageCategory::Notes),
+            OscChannelMessageType::Audio(_)
+            | OscChannelMessageType::AudioBlock(_)
+            | OscChannelMessageType::AudioMeter(_)
+            | OscChannelMessageType::AudioZcr(_)
+            | OscChannelMessageType::SampleCount(_) => Some(MessageCategory::Audio),
+            OscChannelMessageType::Onset(_) => Some(MessageCategory::Onsets),
+            _ => None,
+        }
+    }
+}
 
-    //Exposed Params
-    #[id = "param1"]
-    param1: FloatParam,
-    #[id = "param2"]
-    param2: FloatParam,
-    #[id = "param3"]
-    param3: FloatParam,
-    #[id = "param4"]
-    param4: FloatParam,
-    #[id = "param5"]
-    param5: FloatParam,
-    #[id = "param6"]
-    param6: FloatParam,
-    #[id = "param7"]
-    param7: FloatParam,
-    #[id = "param8"]
-    param8: FloatParam,
+/// What kind of data a message carries, a destination can receive a subset of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageCategory {
+    Params,
+    Notes,
+    Audio,
+    Onsets,
 }
 
-impl Index<usize> for OsClapParams {
-    type Output = FloatParam;
+impl MessageCategory {
+    pub const ALL: [MessageCategory; 4] = [
+        MessageCategory::Params,
+        MessageCategory::Notes,
+        MessageCategory::Audio,
+        MessageCategory::Onsets,
+    ];
 
-    fn index(&self, index: usize) -> &Self::Output {
-        match index {
-            0 => &self.param1,
-            1 => &self.param2,
-            2 => &self.param3,
-            3 => &self.param4,
-            4 => &self.param5,
-            5 => &self.param6,
-            6 => &self.param7,
-            7 => &self.param8,
-            n => panic!("Invalid Parameter index: {}", n)
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageCategory::Params => "Params",
+            MessageCategory::Notes => "Notes",
+            MessageCategory::Audio => "Audio",
+            MessageCategory::Onsets => "Onsets",
         }
     }
 }
 
-impl OsClapParams {
-    #[allow(clippy::derivable_impls)]
-    fn new(
-        p1_dirty: Arc<AtomicBool>,
-        p2_dirty: Arc<AtomicBool>,
-        p3_dirty: Arc<AtomicBool>,
-        p4_dirty: Arc<AtomicBool>,
-        p5_dirty: Arc<AtomicBool>,
-        p6_dirty: Arc<AtomicBool>,
-        p7_dirty: Arc<AtomicBool>,
-        p8_dirty: Arc<AtomicBool>,
-    ) -> Self {
+/// The categories a destination receives, everything by default
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct CategoryMask {
+    pub params: bool,
+    pub notes: bool,
+    pub audio: bool,
+    pub onsets: bool,
+}
+
+impl Default for CategoryMask {
+    fn default() -> Self {
         Self {
-            osc_server_address: RwLock::new("255.255.255.255".to_string()),
-            osc_server_port: RwLock::new(12345),
-            osc_address_base: RwLock::new("osclap".to_string()),
-            flag_send_midi: BoolParam::new("flag_send_midi", true)
-                .hide()
-                .non_automatable(),
-            flag_send_audio: BoolParam::new("flag_send_audio", false)
-                .hide()
-                .non_automatable(),
-            //TODO: handle value change updating resampler ratio
-            osc_sample_rate: IntParam::new(
-                "osc_sample_rate",
-                100,
-                IntRange::Linear { min: 0, max: 1000 },
-            )
-            .hide()
-            .non_automatable(),
-            param1: FloatParam::new("param1", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_step_size(0.001)
-                .with_callback(Arc::new(move |_x| p1_dirty.store(true, Ordering::Release))),
-            param2: FloatParam::new("param2", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_step_size(0.001)
-                .with_callback(Arc::new(move |_x| p2_dirty.store(true, Ordering::Release))),
-            param3: FloatParam::new("param3", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_step_size(0.001)
-                .with_callback(Arc::new(move |_x| p3_dirty.store(true, Ordering::Release))),
-            param4: FloatParam::new("param4", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_step_size(0.001)
-                .with_callback(Arc::new(move |_x| p4_dirty.store(true, Ordering::Release))),
-            param5: FloatParam::new("param5", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_step_size(0.001)
-                .with_callback(Arc::new(move |_x| p5_dirty.store(true, Ordering::Release))),
-            param6: FloatParam::new("param6", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_step_size(0.001)
-                .with_callback(Arc::new(move |_x| p6_dirty.store(true, Ordering::Release))),
-            param7: FloatParam::new("param7", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_step_size(0.001)
-                .with_callback(Arc::new(move |_x| p7_dirty.store(true, Ordering::Release))),
-            param8: FloatParam::new("param8", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_step_size(0.001)
-                .with_callback(Arc::new(move |_x| p8_dirty.store(true, Ordering::Release))),
+            params: true,
+            notes: true,
+            audio: true,
+            onsets: true,
         }
     }
 }
 
-impl Plugin for OsClap {
-    const NAME: &'static str = "OSCLAP";
-    const VENDOR: &'static str = "VanTa";
-    const URL: &'static str = "vanta.xyz";
-    const EMAIL: &'static str = "";
+impl CategoryMask {
+    pub fn allows(&self, category: MessageCategory) -> bool {
+        match category {
+            MessageCategory::Params => self.params,
+            MessageCategory::Notes => self.notes,
+            MessageCategory::Audio => self.audio,
+            MessageCategory::Onsets => self.onsets,
+        }
+    }
 
-    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+    pub fn toggle(&mut self, category: MessageCategory) {
+        let allowed = match category {
+            MessageCategory::Params => &mut self.params,
+            MessageCategory::Notes => &mut self.notes,
+            MessageCategory::Audio => &mut self.audio,
+            MessageCategory::Onsets => &mut self.onsets,
+        };
+        *allowed = !*allowed;
+    }
+}
 
-    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
-    const MIDI_OUTPUT: MidiConfig = MidiConfig::MidiCCs;
-    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
-    const HARD_REALTIME_ONLY: bool = true;
+/// How a param change is sent, relative modes send the change since the last sent value
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ParamSendMode {
+    #[default]
+    Absolute,
+    Relative,
+    /// Absolute once after connecting so the receiver knows where it is, relative after that
+    Hybrid,
+    /// Only a bool when the value crosses the param's comparator threshold
+    Comparator,
+    /// A latching switch, every time the value rises past the middle it flips between 1 and 0
+    Toggle,
+}
 
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
-        main_input_channels: NonZeroU32::new(2),
-        main_output_channels: NonZeroU32::new(2),
+impl ParamSendMode {
+    pub fn next(self) -> Self {
+        match self {
+            ParamSendMode::Absolute => ParamSendMode::Relative,
+            ParamSendMode::Relative => ParamSendMode::Hybrid,
+            ParamSendMode::Hybrid => ParamSendMode::Comparator,
+            ParamSendMode::Comparator => ParamSendMode::Toggle,
+            ParamSendMode::Toggle => ParamSendMode::Absolute,
+        }
+    }
+}
 
-        aux_input_ports: &[],
-        aux_output_ports: &[],
-        names: PortNames::const_default(),
-    }];
+impl fmt::Display for ParamSendMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamSendMode::Absolute => write!(f, "Abs"),
+            ParamSendMode::Relative => write!(f, "Rel"),
+            ParamSendMode::Hybrid => write!(f, "Hyb"),
+            ParamSendMode::Comparator => write!(f, "Cmp"),
+            ParamSendMode::Toggle => write!(f, "Tgl"),
+        }
+    }
+}
 
-    type SysExMessage = ();
-    type BackgroundTask = ();
+/// The CLAP note expression a param drives on every held note, next to its OSC messages
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ParamExpression {
+    #[default]
+    Off,
+    Volume,
+    Pan,
+    Vibrato,
+    Expression,
+    Brightness,
+    Pressure,
+}
 
-    fn params(&self) -> Arc<dyn Params> {
-        nih_trace!("Params Called");
-        self.params.clone() as Arc<dyn Params>
+impl ParamExpression {
+    pub fn next(self) -> Self {
+        match self {
+            ParamExpression::Off => ParamExpression::Volume,
+            ParamExpression::Volume => ParamExpression::Pan,
+            ParamExpression::Pan => ParamExpression::Vibrato,
+            ParamExpression::Vibrato => ParamExpression::Expression,
+            ParamExpression::Expression => ParamExpression::Brightness,
+            ParamExpression::Brightness => ParamExpression::Pressure,
+            ParamExpression::Pressure => ParamExpression::Off,
+        }
     }
 
-    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
-        nih_trace!("Editor Called");
-        editor::create(
-            self.params.clone(),
-            self.sender.clone(),
-            self.editor_state.clone(),
-        )
+    /// The event for one voice from a 0-1 param value, volume tops out at unity gain and pan
+    /// goes from hard left to hard right
+    fn event(self, timing: u32, voice: Voice, value: f32) -> Option<NoteEvent<()>> {
+        let Voice {
+            voice_id,
+            channel,
+            note,
+        } = voice;
+        Some(match self {
+            ParamExpression::Off => return None,
+            ParamExpression::Volume => NoteEvent::PolyVolume {
+                timing,
+                voice_id,
+                channel,
+                note,
+                gain: value,
+            },
+            ParamExpression::Pan => NoteEvent::PolyPan {
+                timing,
+                voice_id,
+                channel,
+                note,
+                pan: value * 2.0 - 1.0,
+            },
+            ParamExpression::Vibrato => NoteEvent::PolyVibrato {
+                timing,
+                voice_id,
+                channel,
+                note,
+                vibrato: value,
+            },
+            ParamExpression::Expression => NoteEvent::PolyExpression {
+                timing,
+                voice_id,
+                channel,
+                note,
+                expression: value,
+            },
+            ParamExpression::Brightness => NoteEvent::PolyBrightness {
+                timing,
+                voice_id,
+                channel,
+                note,
+                brightness: value,
+            },
+            ParamExpression::Pressure => NoteEvent::PolyPressure {
+                timing,
+                voice_id,
+                channel,
+                note,
+                pressure: value,
+            },
+        })
     }
+}
 
-    fn initialize(
-        &mut self,
-        _audio_io_layout: &AudioIOLayout,
-        buffer_config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
-    ) -> bool {
-        nih_trace!("Initialize Called");
-
-        if buffer_config.process_mode != ProcessMode::Realtime {
-            nih_log!("Plugin is not in realtime mode, bailing!");
-            return false;
+impl fmt::Display for ParamExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamExpression::Off => write!(f, "No Expr"),
+            ParamExpression::Volume => write!(f, "Vol"),
+            ParamExpression::Pan => write!(f, "Pan"),
+            ParamExpression::Vibrato => write!(f, "Vib"),
+            ParamExpression::Expression => write!(f, "Expr"),
+            ParamExpression::Brightness => write!(f, "Bright"),
+            ParamExpression::Pressure => write!(f, "Press"),
         }
+    }
+}
 
-        //Setup resampler
-        self.input_sample_rate = buffer_config.sample_rate;
-        self.resampler = match FftFixedOut::<f32>::new(
-            self.input_sample_rate as usize / 100, //TODO: is this right?
-            self.params.osc_sample_rate.value() as usize,
-            100,
-            2,
-            2,
-        ) {
-            Ok(sampler) => Some(sampler),
-            Err(e) => {
-                nih_error!(
-                    "Failed to create resampler, audio processing will be disabled {:?}",
-                    e
-                );
-                None
-            }
-        };
+/// Latch of a param in toggle mode
+#[derive(Debug, Default, Clone, Copy)]
+struct ParamToggle {
+    /// Whether the value was in the upper half, only a rising crossing flips the latch
+    above: bool,
+    on: bool,
+}
 
-        if let Some(resampler) = &self.resampler {
-            self.resampler_buffer = Some(resampler.output_buffer_allocate(true));
+impl ParamToggle {
+    /// The new state when the value just rose past the middle, `None` otherwise
+    fn update(&mut self, value: f32) -> Option<bool> {
+        let above = value >= 0.5;
+        let rising = above && !self.above;
+        self.above = above;
+        if rising {
+            self.on = !self.on;
         }
+        rising.then_some(self.on)
+    }
 
-        //Setup OSC background thread
-        //Dont remake the background thread if its already running
-        if self.osc_thread.is_none() {
-            let socket = match UdpSocket::bind("0.0.0.0:0") {
-                Ok(socket) => socket,
-                Err(e) => {
-                    nih_error!("Failed to bind socket {:?}", e);
-                    return false;
-                }
-            };
-            let ip_port = format!(
-                "{}:{}",
-                *self.params.osc_server_address.read(),
-                *self.params.osc_server_port.read()
-            );
-            nih_trace!("Connecting: {}", ip_port);
-            
-            socket.set_broadcast(true);
-
-            let connect_result = socket.connect(&ip_port);
-            if connect_result.is_err() {
-                nih_error!(
-                    "Failed to connect socket to {} {:?}",
-                    ip_port,
-                    connect_result.unwrap_err()
-                );
-                return false;
-            }
+    fn value(self) -> f32 {
+        if self.on {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
 
-            nih_trace!("Connected!");
-            nih_trace!("Connected to: {}", ip_port);
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ComparatorEdge {
+    #[default]
+    Rising,
+    Falling,
+    Both,
+}
 
-            let address_base = self.params.osc_address_base.read().to_string();
-            nih_trace!("OSC Address Base: {}", address_base);
+impl ComparatorEdge {
+    pub fn next(self) -> Self {
+        match self {
+            ComparatorEdge::Rising => ComparatorEdge::Falling,
+            ComparatorEdge::Falling => ComparatorEdge::Both,
+            ComparatorEdge::Both => ComparatorEdge::Rising,
+        }
+    }
+}
 
-            if let Some(receiver) = std::mem::replace(&mut self.receiver, None) {
-                let client_thread =
-                    thread::spawn(move || osc_client_worker(socket, address_base, receiver));
+impl fmt::Display for ComparatorEdge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComparatorEdge::Rising => write!(f, "Up"),
+            ComparatorEdge::Falling => write!(f, "Down"),
+            ComparatorEdge::Both => write!(f, "Both"),
+        }
+    }
+}
 
-                self.osc_thread = Some(client_thread);
-            } else {
-                nih_error!("Failed get thread channel receiver");
-                return false;
-            }
-        } else {
-            //Threads already alive just update params
-            let connection_send_result =
-                self.sender
-                    .send(OscChannelMessageType::ConnectionChange(OscConnectionType {
-                        ip: self.params.osc_server_address.read().to_string(),
-                        port: *self.params.osc_server_port.read(),
-                    }));
-            if connection_send_result.is_err() {
-                nih_error!(
-                    "Failed to send ConnectionChange update {:?}",
-                    connection_send_result.unwrap_err()
-                );
-            }
-            let address_base = self.params.osc_address_base.read().to_string();
-            nih_trace!("OSC Address Base: {}", address_base);
-            let address_send_result = self.sender.send(OscChannelMessageType::AddressBaseChange(
-                OscAddressBaseType {
-                    address: address_base,
-                },
-            ));
-            if address_send_result.is_err() {
-                nih_error!(
-                    "Failed to send AddressBaseChange update {:?}",
-                    address_send_result.unwrap_err()
-                );
-            }
+/// Turns a param into a trigger in comparator send mode, the threshold is in the range the
+/// value is sent in so it goes from -1 to 1 for bipolar params
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ParamComparator {
+    pub threshold: f32,
+    pub edge: ComparatorEdge,
+}
+
+impl Default for ParamComparator {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            edge: ComparatorEdge::default(),
         }
-        true
     }
+}
 
-    fn deactivate(&mut self) {
-        nih_trace!("Deactivate Called");
-        self.kill_background_thread();
+impl ParamComparator {
+    /// `Some(true)` for a wanted upwards crossing, `Some(false)` for a wanted downwards one
+    fn crossing(&self, previous: f32, value: f32) -> Option<bool> {
+        let rising = previous < self.threshold && value >= self.threshold;
+        let falling = previous >= self.threshold && value < self.threshold;
+        match self.edge {
+            ComparatorEdge::Rising if rising => Some(true),
+            ComparatorEdge::Falling if falling => Some(false),
+            ComparatorEdge::Both if rising || falling => Some(rising),
+            _ => None,
+        }
     }
+}
 
-    fn process(
-        &mut self,
-        buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
+/// Whether packets are getting out, reported by the worker after every connect
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionStatus {
+    /// No connect has finished yet
+    Disconnected,
+    Connected,
+    /// Opening or connecting the transport failed or a stream transport lost its peer, with
+    /// the OS error. Connects are retried in the background
+    ConnectError(String),
+}
+
+/// When each param was last sent over OSC, marked from the audio thread and polled by the editor
+pub struct ParamActivity {
+    epoch: Instant,
+    /// Milliseconds since `epoch` plus one, zero is never sent
+    last_sent: [AtomicU64; NUM_PARAMS],
+}
+
+impl ParamActivity {
+    fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            last_sent: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+
+    pub fn mark(&self, index: usize) {
+        if let Some(last_sent) = self.last_sent.get(index) {
+            let millis = self.epoch.elapsed().as_millis() as u64 + 1;
+            last_sent.store(millis, Ordering::Relaxed);
+        }
+    }
+
+    pub fn since_sent(&self, index: usize) -> Option<Duration> {
+        let millis = self.last_sent.get(index)?.load(Ordering::Relaxed);
+        if millis == 0 {
+            return None;
+        }
+        Some(self.epoch.elapsed().saturating_sub(Duration::from_millis(millis - 1)))
+    }
+}
+
+/// How long process takes per buffer, summed on the audio thread and taken by the editor
+pub struct ProcessTiming {
+    total_micros: AtomicU64,
+    max_micros: AtomicU64,
+    buffers: AtomicU64,
+}
+
+impl ProcessTiming {
+    fn new() -> Self {
+        Self {
+            total_micros: AtomicU64::new(0),
+            max_micros: AtomicU64::new(0),
+            buffers: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, elapsed: Duration) {
+        let micros = elapsed.as_micros() as u64;
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+        self.buffers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Average and max microseconds per buffer since the last take, `None` without buffers
+    pub fn take(&self) -> Option<(u64, u64)> {
+        let buffers = self.buffers.swap(0, Ordering::Relaxed);
+        let total = self.total_micros.swap(0, Ordering::Relaxed);
+        let max = self.max_micros.swap(0, Ordering::Relaxed);
+        (buffers > 0).then(|| (total / buffers, max))
+    }
+}
+
+/// Everything about a param that isn't its value, persisted as one array so a new per-param
+/// setting is just a new field here. Fields missing from older saved states get their default
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct ParamConfig {
+    pub send_mode: ParamSendMode,
+    pub bipolar: bool,
+    pub route: ParamRoute,
+    pub comparator: ParamComparator,
+    /// Changes closer together than this are held back, 0 sends every change
+    pub min_interval_ms: u32,
+    pub expression: ParamExpression,
+}
+
+impl Default for ParamConfig {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl ParamConfig {
+    fn new(index: usize) -> Self {
+        Self {
+            send_mode: ParamSendMode::default(),
+            bipolar: false,
+            route: ParamRoute::new(index),
+            comparator: ParamComparator::default(),
+            min_interval_ms: 0,
+            expression: ParamExpression::default(),
+        }
+    }
+}
+
+/// Where a param change goes, OSC and MIDI CC are enabled independently
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamRoute {
+    pub osc: bool,
+    pub midi: bool,
+    pub cc: u8,
+}
+
+impl ParamRoute {
+    /// OSC only, with the params spread over the undefined CCs from 20 up
+    fn new(index: usize) -> Self {
+        Self {
+            osc: true,
+            midi: false,
+            cc: 20 + index as u8,
+        }
+    }
+}
+
+/// Address names for the CCs that get their own address instead of a generic one
+pub const NAMED_CC_NAMES: [&str; 3] = ["modwheel", "breath", "expression"];
+
+/// A MIDI CC forwarded to `/<base>/<name>`, the CC number can be remapped for controllers that
+/// put the mod wheel or expression pedal somewhere else
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamedCc {
+    pub enabled: bool,
+    pub cc: u8,
+}
+
+impl NamedCc {
+    /// Off, on the standard numbers for the names in `NAMED_CC_NAMES`
+    fn new(index: usize) -> Self {
+        Self {
+            enabled: false,
+            cc: [1, 2, 11][index],
+        }
+    }
+}
+
+/// What param values look like on the wire
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamValueFormat {
+    #[name = "Float 0-1"]
+    Float,
+    /// Scaled to 0-127 ints for receivers that think in MIDI CCs
+    #[name = "MIDI 0-127"]
+    Midi,
+}
+
+impl ParamValueFormat {
+    /// The value that goes out for a 0-1 value. Bipolar floats are mapped to -1..1, MIDI ints
+    /// keep the 0-1 range so they are centered on 64
+    fn output_value(self, value: f32, bipolar: bool) -> f32 {
+        match self {
+            ParamValueFormat::Float if bipolar => value * 2.0 - 1.0,
+            _ => value,
+        }
+    }
+
+    /// MIDI ints are clamped to 0-127 like velocities
+    fn osc_arg(self, value: f32) -> OscType {
+        match self {
+            ParamValueFormat::Float => OscType::Float(value),
+            ParamValueFormat::Midi => {
+                OscType::Int((value * 127.0).round().clamp(0.0, 127.0) as i32)
+            }
+        }
+    }
+
+    /// The change between two values, MIDI ints go from -127 to 127
+    fn osc_delta_arg(self, delta: f32) -> OscType {
+        match self {
+            ParamValueFormat::Float => OscType::Float(delta),
+            ParamValueFormat::Midi => {
+                OscType::Int((delta * 127.0).round().clamp(-127.0, 127.0) as i32)
+            }
+        }
+    }
+}
+
+/// What the receiver is caught up on after a failed connect is followed by a working one
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconnectResend {
+    #[name = "Nothing"]
+    Nothing,
+    /// Only params that changed while there was no connection
+    #[name = "Changed"]
+    Changed,
+    /// Every param that was sent before, for receivers that lose their state with the connection
+    #[name = "All"]
+    All,
+}
+
+/// What `Send Audio` sends
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioMode {
+    /// The resampled signal
+    #[name = "Samples"]
+    Samples,
+    /// Peak and RMS of every host buffer, for meters. Nothing is resampled
+    #[name = "Meter"]
+    Meter,
+}
+
+/// How often params are sent when they follow the host's clock instead of their changes
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockDivision {
+    #[name = "Off"]
+    Off,
+    #[name = "1/4"]
+    Quarter,
+    #[name = "1/8"]
+    Eighth,
+    #[name = "1/16"]
+    Sixteenth,
+}
+
+impl ClockDivision {
+    /// Ticks per quarter note, `None` when params aren't clocked
+    pub fn ticks_per_beat(self) -> Option<f64> {
+        match self {
+            ClockDivision::Off => None,
+            ClockDivision::Quarter => Some(1.0),
+            ClockDivision::Eighth => Some(2.0),
+            ClockDivision::Sixteenth => Some(4.0),
+        }
+    }
+}
+
+/// A persisted setting that counts how often the host restored it. nih_plug only goes through
+/// `PersistentField::set` when it loads state, so the editor writing through the lock doesn't
+/// count
+struct Persisted<T> {
+    value: RwLock<T>,
+    loads: Arc<AtomicU32>,
+}
+
+impl<T> Persisted<T> {
+    fn new(value: T, loads: &Arc<AtomicU32>) -> Self {
+        Self {
+            value: RwLock::new(value),
+            loads: loads.clone(),
+        }
+    }
+}
+
+impl<T> Deref for Persisted<T> {
+    type Target = RwLock<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<'a, T> PersistentField<'a, T> for Persisted<T>
+where
+    T: Serialize + Deserialize<'a> + Send + Sync,
+{
+    fn set(&self, new_value: T) {
+        *self.value.write() = new_value;
+        self.loads.fetch_add(1, Ordering::Release);
+    }
+
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&T) -> R,
+    {
+        f(&self.value.read())
+    }
+}
+
+#[derive(Params)]
+pub struct OsClapParams {
+    //Persisted Settings
+    #[persist = "osc_server_address"]
+    osc_server_address: Persisted<String>,
+    #[persist = "osc_server_port"]
+    osc_server_port: Persisted<u16>,
+    /// Port param values are received on, 0 turns receiving off
+    #[persist = "osc_recv_port"]
+    osc_recv_port: Persisted<u16>,
+    #[persist = "osc_extra_destinations"]
+    osc_extra_destinations: Persisted<Vec<String>>,
+    #[persist = "osc_bind_address"]
+    osc_bind_address: Persisted<String>,
+    /// 0 for whatever port the OS picks
+    #[persist = "osc_bind_port"]
+    osc_bind_port: Persisted<u16>,
+    #[persist = "osc_baud_rate"]
+    osc_baud_rate: Persisted<u32>,
+    #[persist = "osc_address_base"]
+    osc_address_base: Persisted<String>,
+    #[persist = "osc_transport"]
+    osc_transport: Persisted<OscTransportMode>,
+    #[persist = "osc_categories"]
+    osc_categories: Persisted<CategoryMask>,
+    /// Held while the connection fields are written or read and the matching ConnectionChange
+    /// is sent, so the messages reach the worker in the same order the values were stored
+    connection_guard: Mutex<()>,
+    #[persist = "param_configs"]
+    param_configs: Persisted<[ParamConfig; NUM_PARAMS]>,
+    #[persist = "named_ccs"]
+    named_ccs: Persisted<[NamedCc; 3]>,
+    /// What each param is called in its address, blank for the param's own name
+    #[persist = "param_addresses"]
+    param_addresses: Persisted<[String; NUM_PARAMS]>,
+    /// What each param is received under, blank for the name it is sent under
+    #[persist = "param_receive_addresses"]
+    param_receive_addresses: Persisted<[String; NUM_PARAMS]>,
+    /// Goes up whenever the host loads a preset or project, the worker and the editor compare
+    /// it to the last value they applied since loading doesn't always call initialize
+    settings_loads: Arc<AtomicU32>,
+    /// Set when `osc_sample_rate` changes, the audio thread has a resampler built for it
+    osc_sample_rate_dirty: Arc<AtomicBool>,
+
+    //Setting Flags
+    #[id = "flag_send_midi"]
+    flag_send_midi: BoolParam,
+    #[id = "flag_split_notes"]
+    flag_split_notes: BoolParam,
+    #[id = "flag_zero_velocity_off"]
+    flag_zero_velocity_off: BoolParam,
+    #[id = "flag_midi_int_values"]
+    flag_midi_int_values: BoolParam,
+    #[id = "flag_note_trigger"]
+    flag_note_trigger: BoolParam,
+    #[id = "note_range_low"]
+    note_range_low: IntParam,
+    #[id = "note_range_high"]
+    note_range_high: IntParam,
+    #[id = "param_value_format"]
+    param_value_format: EnumParam<ParamValueFormat>,
+    #[id = "flag_slugify_names"]
+    flag_slugify_names: BoolParam,
+    #[id = "flag_send_previous"]
+    flag_send_previous: BoolParam,
+    #[id = "flag_bundle_params"]
+    flag_bundle_params: BoolParam,
+    /// Params go out as 0-1 over their range instead of their plain value
+    #[id = "flag_normalized_params"]
+    flag_normalized_params: BoolParam,
+    /// Most OSC sends per second of each param, 0 for no limit
+    #[id = "param_max_rate"]
+    param_max_rate: IntParam,
+    #[id = "flag_send_audio"]
+    flag_send_audio: BoolParam,
+    #[id = "flag_send_zcr"]
+    flag_send_zcr: BoolParam,
+    #[id = "flag_send_samples"]
+    flag_send_samples: BoolParam,
+    #[id = "flag_send_transport"]
+    flag_send_transport: BoolParam,
+    #[id = "audio_mode"]
+    audio_mode: EnumParam<AudioMode>,
+    /// Meter levels for every channel instead of one across all of them
+    #[id = "flag_meter_per_channel"]
+    flag_meter_per_channel: BoolParam,
+    #[id = "flag_audio_blob"]
+    flag_audio_blob: BoolParam,
+    /// Silent samples are left out of the per sample stream
+    #[id = "flag_skip_silence"]
+    flag_skip_silence: BoolParam,
+    #[id = "osc_sample_rate"]
+    osc_sample_rate: IntParam,
+    #[id = "connection_debounce"]
+    connection_debounce: IntParam,
+    #[id = "osc_ttl"]
+    osc_ttl: IntParam,
+    #[id = "reconnect_resend"]
+    reconnect_resend: EnumParam<ReconnectResend>,
+    #[id = "osc_time_offset"]
+    osc_time_offset: IntParam,
+    #[id = "flag_hello_handshake"]
+    flag_hello_handshake: BoolParam,
+    #[id = "flag_heartbeat"]
+    flag_heartbeat: BoolParam,
+    /// Time between heartbeats in ms
+    #[id = "heartbeat_interval"]
+    heartbeat_interval: IntParam,
+    #[id = "flag_sample_accurate"]
+    flag_sample_accurate: BoolParam,
+    /// Ramp between automation points in sample accurate mode instead of jumping
+    #[id = "flag_param_smoothing"]
+    flag_param_smoothing: BoolParam,
+    #[id = "flag_quiet_on_load"]
+    flag_quiet_on_load: BoolParam,
+    #[id = "flag_param_digest"]
+    flag_param_digest: BoolParam,
+    #[id = "flag_digest_names"]
+    flag_digest_names: BoolParam,
+    #[id = "flag_process_timing"]
+    flag_process_timing: BoolParam,
+    #[id = "flag_compress_repeats"]
+    flag_compress_repeats: BoolParam,
+    #[id = "flag_high_priority"]
+    flag_high_priority: BoolParam,
+    #[id = "clock_division"]
+    clock_division: EnumParam<ClockDivision>,
+    #[id = "arp_rate"]
+    arp_rate: EnumParam<ClockDivision>,
+    #[id = "arp_pattern"]
+    arp_pattern: EnumParam<ArpPattern>,
+    #[id = "arp_octaves"]
+    arp_octaves: IntParam,
+
+    //Analysis
+    #[id = "analysis_channel"]
+    analysis_channel: EnumParam<AnalysisChannel>,
+
+    //Onset Detection
+    #[id = "flag_send_onsets"]
+    flag_send_onsets: BoolParam,
+    #[id = "onset_low_edge"]
+    onset_low_edge: FloatParam,
+    #[id = "onset_high_edge"]
+    onset_high_edge: FloatParam,
+    #[id = "onset_low_sensitivity"]
+    onset_low_sensitivity: FloatParam,
+    #[id = "onset_mid_sensitivity"]
+    onset_mid_sensitivity: FloatParam,
+    #[id = "onset_high_sensitivity"]
+    onset_high_sensitivity: FloatParam,
+
+    //Audio Level as MIDI CC
+    #[id = "flag_level_cc"]
+    flag_level_cc: BoolParam,
+    #[id = "level_cc"]
+    level_cc: IntParam,
+    #[id = "level_cc_channel"]
+    level_cc_channel: IntParam,
+    #[id = "level_cc_rate"]
+    level_cc_rate: IntParam,
+    #[id = "level_mapping"]
+    level_mapping: EnumParam<LevelMapping>,
+
+    //Exposed Params
+    #[nested]
+    exposed: ExposedParams,
+}
+
+/// How many params are exposed to the host
+pub const NUM_PARAMS: usize = 8;
+
+/// The exposed params as an array. The ids are mapped by hand to `param1`, `param2`, ... like
+/// the fields they used to be, so params in existing projects keep their values
+struct ExposedParams {
+    params: [FloatParam; NUM_PARAMS],
+}
+
+impl ExposedParams {
+    fn new(param_dirty: &Arc<[AtomicBool; NUM_PARAMS]>) -> Self {
+        Self {
+            params: std::array::from_fn(|index| {
+                let param_dirty = param_dirty.clone();
+                FloatParam::new(
+                    format!("param{}", index + 1),
+                    0.0,
+                    FloatRange::Linear { min: 0.0, max: 1.0 },
+                )
+                .with_step_size(0.001)
+                .with_poly_modulation_id(index as u32)
+                .with_smoother(SmoothingStyle::Linear(PARAM_SMOOTHING_MS))
+                .with_callback(Arc::new(move |_x| {
+                    param_dirty[index].store(true, Ordering::Release)
+                }))
+            }),
+        }
+    }
+}
+
+unsafe impl Params for ExposedParams {
+    fn param_map(&self) -> Vec<(String, ParamPtr, String)> {
+        self.params
+            .iter()
+            .enumerate()
+            .map(|(index, param)| (format!("param{}", index + 1), param.as_ptr(), String::new()))
+            .collect()
+    }
+}
+
+impl Index<usize> for OsClapParams {
+    type Output = FloatParam;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.exposed.params[index]
+    }
+}
+
+impl OsClapParams {
+    fn get(&self, index: usize) -> Option<&FloatParam> {
+        self.exposed.params.get(index)
+    }
+
+    #[allow(clippy::derivable_impls)]
+    fn new(param_dirty: &Arc<[AtomicBool; NUM_PARAMS]>) -> Self {
+        let settings_loads = Arc::new(AtomicU32::new(0));
+        let osc_sample_rate_dirty = Arc::new(AtomicBool::new(false));
+        Self {
+            osc_server_address: Persisted::new("255.255.255.255".to_string(), &settings_loads),
+            osc_server_port: Persisted::new(12345, &settings_loads),
+            osc_recv_port: Persisted::new(0, &settings_loads),
+            osc_extra_destinations: Persisted::new(Vec::new(), &settings_loads),
+            osc_bind_address: Persisted::new(
+                transport::DEFAULT_BIND_ADDRESS.to_string(),
+                &settings_loads,
+            ),
+            osc_bind_port: Persisted::new(0, &settings_loads),
+            osc_baud_rate: Persisted::new(transport::DEFAULT_BAUD_RATE, &settings_loads),
+            osc_address_base: Persisted::new("osclap".to_string(), &settings_loads),
+            osc_transport: Persisted::new(OscTransportMode::Udp, &settings_loads),
+            osc_categories: Persisted::new(CategoryMask::default(), &settings_loads),
+            connection_guard: Mutex::new(()),
+            param_configs: Persisted::new(std::array::from_fn(ParamConfig::new), &settings_loads),
+            named_ccs: Persisted::new(std::array::from_fn(NamedCc::new), &settings_loads),
+            param_addresses: Persisted::new(Default::default(), &settings_loads),
+            param_receive_addresses: Persisted::new(Default::default(), &settings_loads),
+            settings_loads,
+            osc_sample_rate_dirty: osc_sample_rate_dirty.clone(),
+            flag_send_midi: BoolParam::new("flag_send_midi", true)
+                .hide()
+                .non_automatable(),
+            flag_split_notes: BoolParam::new("flag_split_notes", false)
+                .hide()
+                .non_automatable(),
+            flag_zero_velocity_off: BoolParam::new("flag_zero_velocity_off", true)
+                .hide()
+                .non_automatable(),
+            flag_midi_int_values: BoolParam::new("flag_midi_int_values", false)
+                .hide()
+                .non_automatable(),
+            flag_note_trigger: BoolParam::new("flag_note_trigger", false)
+                .hide()
+                .non_automatable(),
+            note_range_low: IntParam::new("note_range_low", 0, IntRange::Linear { min: 0, max: 127 })
+                .with_value_to_string(formatters::v2s_i32_note_formatter())
+                .with_string_to_value(formatters::s2v_i32_note_formatter())
+                .hide()
+                .non_automatable(),
+            note_range_high: IntParam::new(
+                "note_range_high",
+                127,
+                IntRange::Linear { min: 0, max: 127 },
+            )
+            .with_value_to_string(formatters::v2s_i32_note_formatter())
+            .with_string_to_value(formatters::s2v_i32_note_formatter())
+            .hide()
+            .non_automatable(),
+            param_value_format: EnumParam::new("param_value_format", ParamValueFormat::Float)
+                .hide()
+                .non_automatable(),
+            flag_slugify_names: BoolParam::new("flag_slugify_names", false)
+                .hide()
+                .non_automatable(),
+            flag_send_previous: BoolParam::new("flag_send_previous", false)
+                .hide()
+                .non_automatable(),
+            flag_bundle_params: BoolParam::new("flag_bundle_params", false)
+                .hide()
+                .non_automatable(),
+            flag_normalized_params: BoolParam::new("flag_normalized_params", false)
+                .hide()
+                .non_automatable(),
+            param_max_rate: IntParam::new("param_max_rate", 0, IntRange::Linear { min: 0, max: 200 })
+                .with_unit(" Hz")
+                .hide()
+                .non_automatable(),
+            flag_send_audio: BoolParam::new("flag_send_audio", false)
+                .hide()
+                .non_automatable(),
+            flag_send_zcr: BoolParam::new("flag_send_zcr", false)
+                .hide()
+                .non_automatable(),
+            flag_send_samples: BoolParam::new("flag_send_samples", false)
+                .hide()
+                .non_automatable(),
+            flag_send_transport: BoolParam::new("flag_send_transport", false)
+                .hide()
+                .non_automatable(),
+            audio_mode: EnumParam::new("audio_mode", AudioMode::Samples)
+                .hide()
+                .non_automatable(),
+            flag_meter_per_channel: BoolParam::new("flag_meter_per_channel", false)
+                .hide()
+                .non_automatable(),
+            flag_audio_blob: BoolParam::new("flag_audio_blob", false)
+                .hide()
+                .non_automatable(),
+            flag_skip_silence: BoolParam::new("flag_skip_silence", false)
+                .hide()
+                .non_automatable(),
+            osc_sample_rate: IntParam::new(
+                "osc_sample_rate",
+                100,
+                IntRange::Linear { min: 0, max: 1000 },
+            )
+            .hide()
+            .non_automatable()
+            .with_callback(Arc::new(move |_x| {
+                osc_sample_rate_dirty.store(true, Ordering::Release)
+            })),
+            connection_debounce: IntParam::new(
+                "connection_debounce",
+                250,
+                IntRange::Linear { min: 0, max: 2000 },
+            )
+            .with_unit(" ms")
+            .hide()
+            .non_automatable(),
+            //1 keeps packets on the local network
+            osc_ttl: IntParam::new("osc_ttl", 1, IntRange::Linear { min: 1, max: 255 })
+                .hide()
+                .non_automatable(),
+            reconnect_resend: EnumParam::new("reconnect_resend", ReconnectResend::Changed)
+                .hide()
+                .non_automatable(),
+            osc_time_offset: IntParam::new(
+                "osc_time_offset",
+                0,
+                IntRange::Linear {
+                    min: -1000,
+                    max: 1000,
+                },
+            )
+            .with_unit(" ms")
+            .hide()
+            .non_automatable(),
+            flag_hello_handshake: BoolParam::new("flag_hello_handshake", false)
+                .hide()
+                .non_automatable(),
+            flag_heartbeat: BoolParam::new("flag_heartbeat", false)
+                .hide()
+                .non_automatable(),
+            heartbeat_interval: IntParam::new(
+                "heartbeat_interval",
+                1000,
+                IntRange::Linear { min: 100, max: 10000 },
+            )
+            .with_unit(" ms")
+            .hide()
+            .non_automatable(),
+            flag_sample_accurate: BoolParam::new("flag_sample_accurate", false)
+                .hide()
+                .non_automatable(),
+            flag_param_smoothing: BoolParam::new("flag_param_smoothing", false)
+                .hide()
+                .non_automatable(),
+            flag_quiet_on_load: BoolParam::new("flag_quiet_on_load", false)
+                .hide()
+                .non_automatable(),
+            flag_param_digest: BoolParam::new("flag_param_digest", false)
+                .hide()
+                .non_automatable(),
+            flag_digest_names: BoolParam::new("flag_digest_names", false)
+                .hide()
+                .non_automatable(),
+            flag_process_timing: BoolParam::new("flag_process_timing", false)
+                .hide()
+                .non_automatable(),
+            flag_compress_repeats: BoolParam::new("flag_compress_repeats", false)
+                .hide()
+                .non_automatable(),
+            flag_high_priority: BoolParam::new("flag_high_priority", false)
+                .hide()
+                .non_automatable(),
+            clock_division: EnumParam::new("clock_division", ClockDivision::Off)
+                .hide()
+                .non_automatable(),
+            arp_rate: EnumParam::new("arp_rate", ClockDivision::Off)
+                .hide()
+                .non_automatable(),
+            arp_pattern: EnumParam::new("arp_pattern", ArpPattern::Up)
+                .hide()
+                .non_automatable(),
+            arp_octaves: IntParam::new("arp_octaves", 1, IntRange::Linear { min: 1, max: 4 })
+                .hide()
+                .non_automatable(),
+            analysis_channel: EnumParam::new("analysis_channel", AnalysisChannel::Sum)
+                .hide()
+                .non_automatable(),
+            flag_send_onsets: BoolParam::new("flag_send_onsets", false)
+                .hide()
+                .non_automatable(),
+            onset_low_edge: FloatParam::new(
+                "onset_low_edge",
+                150.0,
+                FloatRange::Linear {
+                    min: 20.0,
+                    max: 1000.0,
+                },
+            )
+            .with_step_size(1.0)
+            .with_unit(" Hz")
+            .hide()
+            .non_automatable(),
+            onset_high_edge: FloatParam::new(
+                "onset_high_edge",
+                2500.0,
+                FloatRange::Linear {
+                    min: 1000.0,
+                    max: 10000.0,
+                },
+            )
+            .with_step_size(1.0)
+            .with_unit(" Hz")
+            .hide()
+            .non_automatable(),
+            onset_low_sensitivity: FloatParam::new(
+                "onset_low_sensitivity",
+                0.5,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_step_size(0.01)
+            .hide()
+            .non_automatable(),
+            onset_mid_sensitivity: FloatParam::new(
+                "onset_mid_sensitivity",
+                0.5,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_step_size(0.01)
+            .hide()
+            .non_automatable(),
+            onset_high_sensitivity: FloatParam::new(
+                "onset_high_sensitivity",
+                0.5,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_step_size(0.01)
+            .hide()
+            .non_automatable(),
+            flag_level_cc: BoolParam::new("flag_level_cc", false)
+                .hide()
+                .non_automatable(),
+            level_cc: IntParam::new("level_cc", 28, IntRange::Linear { min: 0, max: 127 })
+                .hide()
+                .non_automatable(),
+            level_cc_channel: IntParam::new(
+                "level_cc_channel",
+                1,
+                IntRange::Linear { min: 1, max: 16 },
+            )
+            .hide()
+            .non_automatable(),
+            level_cc_rate: IntParam::new("level_cc_rate", 30, IntRange::Linear { min: 1, max: 100 })
+                .with_unit(" Hz")
+                .hide()
+                .non_automatable(),
+            level_mapping: EnumParam::new("level_mapping", LevelMapping::Decibels)
+                .hide()
+                .non_automatable(),
+            exposed: ExposedParams::new(param_dirty),
+        }
+    }
+
+    /// The persisted connection as one value, take `connection_guard` around it
+    fn connection(&self) -> OscConnectionType {
+        OscConnectionType {
+            transport: *self.osc_transport.read(),
+            ip: self.osc_server_address.read().to_string(),
+            port: *self.osc_server_port.read(),
+            handshake: self.flag_hello_handshake.value(),
+            ttl: self.osc_ttl.value() as u32,
+            baud_rate: *self.osc_baud_rate.read(),
+            resend: self.reconnect_resend.value(),
+            recv_port: *self.osc_recv_port.read(),
+            extra_destinations: self.osc_extra_destinations.read().clone(),
+            bind_address: self.osc_bind_address.read().to_string(),
+            bind_port: *self.osc_bind_port.read(),
+        }
+    }
+
+    /// Take `connection_guard` around this and sending the ConnectionChange
+    fn store_connection(&self, connection: &OscConnectionType) {
+        *self.osc_transport.write() = connection.transport;
+        *self.osc_server_address.write() = connection.ip.clone();
+        *self.osc_server_port.write() = connection.port;
+        *self.osc_recv_port.write() = connection.recv_port;
+        *self.osc_extra_destinations.write() = connection.extra_destinations.clone();
+        *self.osc_bind_address.write() = connection.bind_address.clone();
+        *self.osc_bind_port.write() = connection.bind_port;
+        *self.osc_baud_rate.write() = connection.baud_rate;
+    }
+
+    /// The value of param `index` `steps` samples on, ramped between automation points only
+    /// with Smooth Params
+    fn step_param(&self, index: usize, steps: u32) -> f32 {
+        //Stepped either way so turning smoothing on doesn't replay a stale ramp
+        let smoothed = self[index].smoothed.next_step(steps);
+        if self.flag_param_smoothing.value() {
+            smoothed
+        } else {
+            self[index].value()
+        }
+    }
+
+    /// Stores the connection and queues its ConnectionChange in one go, initialize can't slip
+    /// in between with half of the old values
+    fn commit_connection(
+        &self,
+        connection: OscConnectionType,
+        sender: &Sender<OscChannelMessageType>,
+    ) -> Result<()> {
+        let _guard = self.connection_guard.lock();
+        self.store_connection(&connection);
+        sender.send(OscChannelMessageType::ConnectionChange(connection))?;
+        Ok(())
+    }
+}
+
+/// Frames per channel the resampler puts out per call, audio blobs carry one call each
+const AUDIO_BLOCK_FRAMES: usize = 100;
+
+/// Stereo, the widest input layout
+const MAX_AUDIO_CHANNELS: usize = 2;
+
+/// Audio blocks the worker can be behind before new ones are dropped
+const AUDIO_BLOCK_POOL_SIZE: usize = 256;
+
+/// Follows the host's position and tells when it crosses a tick of a beat division. Ticks fall
+/// on multiples of the division counted from the start of the song, so they line up with the
+/// host's grid
+#[derive(Default)]
+struct BeatClock {
+    /// Last tick that was reported, `None` while the host isn't playing
+    tick: Option<i64>,
+}
+
+impl BeatClock {
+    /// Whether a tick falls in this buffer, several ticks in one buffer are reported once
+    fn ticked(
+        &mut self,
+        transport: &Transport,
+        num_samples: usize,
+        sample_rate: f32,
+        ticks_per_beat: f64,
+    ) -> bool {
+        let (Some(pos_beats), Some(tempo)) = (transport.pos_beats(), transport.tempo) else {
+            self.tick = None;
+            return false;
+        };
+        if !transport.playing {
+            self.tick = None;
+            return false;
+        }
+        let buffer_beats = num_samples as f64 / sample_rate as f64 * tempo / 60.0;
+        let start = pos_beats * ticks_per_beat;
+        let end = start + buffer_beats * ticks_per_beat;
+        //The last tick that starts inside this buffer, a tick sitting exactly on the start
+        //of the buffer counts as well so playing from the top sends right away
+        let tick = (end.ceil() - 1.0) as i64;
+        if (tick as f64) < start || self.tick == Some(tick) {
+            return false;
+        }
+        self.tick = Some(tick);
+        true
+    }
+
+    fn is_running(&self) -> bool {
+        self.tick.is_some()
+    }
+}
+
+/// Ramp time between automation points with Smooth Params
+const PARAM_SMOOTHING_MS: f32 = 10.0;
+
+/// How long param changes are held back after initialize with Quiet On Load, long enough for
+/// the host to finish restoring the project state
+const LOAD_GRACE_SECONDS: f32 = 0.5;
+
+/// How long deactivate waits for the worker to stop, a stuck one is left behind
+const WORKER_STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+impl Plugin for OsClap {
+    const NAME: &'static str = "OSCLAP";
+    const VENDOR: &'static str = "VanTa";
+    const URL: &'static str = "vanta.xyz";
+    const EMAIL: &'static str = "";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::MidiCCs;
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+    const HARD_REALTIME_ONLY: bool = true;
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+
+            aux_input_ports: &[],
+            aux_output_ports: &[],
+            names: PortNames::const_default(),
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(1),
+            main_output_channels: NonZeroU32::new(1),
+
+            aux_input_ports: &[],
+            aux_output_ports: &[],
+            names: PortNames::const_default(),
+        },
+    ];
+
+    type SysExMessage = ();
+    type BackgroundTask = OsClapTask;
+
+    fn params(&self) -> Arc<dyn Params> {
+        nih_trace!("Params Called");
+        self.params.clone() as Arc<dyn Params>
+    }
+
+    fn task_executor(&mut self) -> TaskExecutor<Self> {
+        let resampler_swap = self.resampler_swap.clone();
+        Box::new(move |task| match task {
+            OsClapTask::BuildResampler(settings) => {
+                let built = build_resampler(settings);
+                let mut swap = resampler_swap.lock();
+                //Whatever the audio thread handed back is dropped here instead
+                swap.retired = None;
+                swap.built = Some((settings, built));
+            }
+        })
+    }
+
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        nih_trace!("Editor Called");
+        editor::create(
+            self.params.clone(),
+            editor::EditorChannels {
+                sender: self.sender.clone(),
+                incoming: self.incoming.receiver.clone(),
+                received: self.received.receiver.clone(),
+                dropped: self.realtime_sender.dropped.clone(),
+                send_stats: self.send_stats.clone(),
+            },
+            self.handshake_state.clone(),
+            self.connection_status.clone(),
+            self.param_activity.clone(),
+            self.process_timing.clone(),
+            self.editor_state.clone(),
+        )
+    }
+
+    fn filter_state(state: &mut PluginState) {
+        migrate_param_configs(state);
+    }
+
+    fn initialize(
+        &mut self,
+        audio_io_layout: &AudioIOLayout,
+        buffer_config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        nih_trace!("Initialize Called");
+
+        if buffer_config.process_mode != ProcessMode::Realtime {
+            nih_log!("Plugin is not in realtime mode, bailing!");
+            return false;
+        }
+
+        //Setup resampler
+        self.input_sample_rate = buffer_config.sample_rate;
+        self.max_buffer_size = buffer_config.max_buffer_size as usize;
+        //Every layout has an input
+        self.input_channels = audio_io_layout
+            .main_input_channels
+            .map_or(1, |channels| channels.get() as usize);
+        self.params.osc_sample_rate_dirty.store(false, Ordering::Release);
+        self.resampler_input_start = 0;
+        *self.resampler_swap.lock() = ResamplerSwap::default();
+        self.resampler = build_resampler(self.resampler_settings());
+
+        self.onset_detector = Some(OnsetDetector::new(self.input_sample_rate));
+        self.sample_count = 0;
+        self.sent_transport = OscTransportType::default();
+        self.voices.clear();
+        self.expression_values = [f32::NAN; NUM_PARAMS];
+
+        self.load_grace_samples = if self.params.flag_quiet_on_load.value() {
+            (self.input_sample_rate * LOAD_GRACE_SECONDS) as usize
+        } else {
+            0
+        };
+
+        //Whatever the host loaded before this is picked up below
+        self.applied_settings_loads = self.params.settings_loads.load(Ordering::Acquire);
+
+        //Setup OSC background thread
+        //A worker that died on its own is cleaned up so it gets replaced below
+        if matches!(&self.osc_thread, Some(osc_thread) if osc_thread.is_finished()) {
+            nih_error!("Background thread stopped unexpectedly, restarting it");
+            self.kill_background_thread();
+        }
+        //Dont remake the background thread if its already running
+        if self.osc_thread.is_none() {
+            let connection = {
+                let _guard = self.params.connection_guard.lock();
+                self.params.connection()
+            };
+            let transport_mode = connection.transport;
+            nih_trace!("Connecting: {}:{} over {}", connection.ip, connection.port, transport_mode);
+
+            //The plugin still loads without networking so the editor can show what went wrong,
+            //the next initialize tries again. Connecting is up to the worker, which keeps trying
+            //until the receiver is there. It also binds to the configured address, a wrong one
+            //is reported and can be fixed in the editor
+            let transport = match transport::open_transport(
+                transport_mode,
+                connection.ttl,
+                transport::DEFAULT_BIND_ADDRESS,
+                0,
+            ) {
+                Ok(transport) => transport,
+                Err(e) => {
+                    nih_error!("Failed to open {} transport {:?}", transport_mode, e);
+                    *self.connection_status.write() = ConnectionStatus::ConnectError(e.to_string());
+                    return true;
+                }
+            };
+            *self.connection_status.write() = ConnectionStatus::Disconnected;
+
+            let address_base = self.params.osc_address_base.read().to_string();
+            nih_trace!("OSC Address Base: {}", address_base);
+
+            let receiver = self.receiver.clone();
+            let info = self.info();
+            let categories = *self.params.osc_categories.read();
+            self.osc_thread_stop = Arc::new(AtomicBool::new(false));
+            let status = WorkerStatus {
+                handshake_state: self.handshake_state.clone(),
+                incoming: self.incoming.sender.clone(),
+                received: self.received.sender.clone(),
+                connection_status: self.connection_status.clone(),
+                stop: self.osc_thread_stop.clone(),
+            };
+            let transport = ObservedTransport::new(
+                transport,
+                self.packet_observer.clone(),
+                self.send_stats.clone(),
+            );
+            let high_priority = self.params.flag_high_priority.value();
+            let params = self.params.clone();
+            let (done_sender, done_receiver) = crossbeam_channel::bounded::<()>(0);
+            self.osc_thread_done = done_receiver;
+            let client_thread = thread::spawn(move || {
+                //Dropped when the worker returns or panics
+                let _done = done_sender;
+                if high_priority {
+                    raise_worker_priority();
+                }
+                osc_client_worker(
+                    transport,
+                    params,
+                    address_base,
+                    info,
+                    connection,
+                    status,
+                    categories,
+                    receiver,
+                )
+            });
+
+            self.osc_thread = Some(client_thread);
+            self.compress_repeats = None;
+        } else {
+            //Threads already alive just update params
+            self.push_settings();
+        }
+        true
+    }
+
+    fn deactivate(&mut self) {
+        nih_trace!("Deactivate Called");
+        self.kill_background_thread();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        //Networking is disabled, nothing would ever take the messages off the channel. Events,
+        //voices and the MIDI CC routes still run, only the OSC sends are left out
+        let networking = self.osc_thread.is_some();
+        self.realtime_sender.enabled = networking;
+        //A preset was loaded without an initialize, only happens on preset recall so the
+        //allocations of the settings messages are fine here
+        let settings_loads = self.params.settings_loads.load(Ordering::Acquire);
+        if networking && settings_loads != self.applied_settings_loads {
+            self.applied_settings_loads = settings_loads;
+            nih_trace!("Settings loaded, updating the worker");
+            self.push_settings();
+        }
+        let compress_repeats = self.params.flag_compress_repeats.value();
+        if networking && self.compress_repeats != Some(compress_repeats) {
+            self.compress_repeats = Some(compress_repeats);
+            let send_result = self
+                .sender
+                .send(OscChannelMessageType::RepeatsChange(compress_repeats));
+            if let Err(e) = send_result {
+                nih_error!("Failed to send RepeatsChange update {:?}", e);
+            }
+        }
+        //Only read the clock when someone is looking
+        let process_start = self.params.flag_process_timing.value().then(Instant::now);
+        //Position of the first sample in this buffer, sent first so everything below is after it
+        if self.params.flag_send_samples.value() {
+            let count_result = self
+                .realtime_sender
+                .send(OscChannelMessageType::SampleCount(OscSampleCountType {
+                    samples: self.sample_count,
+                }));
+            if let Err(e) = count_result {
+                nih_error!("Failed to send sample count {:?}", e);
+            }
+        }
+        if self.params.flag_send_transport.value() {
+            let transport_result = self.process_transport(context.transport());
+            if let Err(e) = transport_result {
+                nih_error!("Failed to send transport {:?}", e);
+            }
+        } else {
+            //Everything goes out again once it is turned back on
+            self.sent_transport = OscTransportType::default();
+        }
+        //Process Dirty Params
+        if let Some(param_configs) = self.params.param_configs.try_read() {
+            self.param_configs = *param_configs;
+        }
+        if let Some(named_ccs) = self.params.named_ccs.try_read() {
+            self.named_ccs = *named_ccs;
+        }
+        self.normalized_params = self.params.flag_normalized_params.value();
+        let param_result = if self.load_grace_samples > 0 {
+            self.skip_loaded_params(buffer.samples());
+            Ok(())
+        } else if self.params.flag_param_digest.value() {
+            //Dirty params still drive the MIDI CC routes, OSC only gets the digest
+            self.process_params(context).and_then(|_| self.send_param_digest())
+        } else if let Some(ticks_per_beat) = self.params.clock_division.value().ticks_per_beat() {
+            let ticked = self.param_clock.ticked(
+                context.transport(),
+                buffer.samples(),
+                self.input_sample_rate,
+                ticks_per_beat,
+            );
+            self.process_params(context)
+                .and_then(|_| if ticked { self.send_all_params() } else { Ok(()) })
+        } else if self.params.flag_sample_accurate.value() {
+            self.process_params_sample_accurate(buffer.samples(), context)
+        } else {
+            self.process_params(context)
+        };
+        if param_result.is_err() {
+            nih_error!("Failed to send params {:?}", param_result.unwrap_err());
+        }
+        //Process Note Events
+        let send_midi = self.params.flag_send_midi.value();
+        while let Some(event) = context.next_event() {
+            nih_trace!("NoteEvent: {:?}", event);
+            let event = self.zero_velocity_note_off(event);
+            if let Err(e) = self.track_voice(&event, context) {
+                nih_error!("Failed to send note expressions {:?}", e);
+            }
+            let message_result = self.process_event(&event, send_midi);
+            if let Err(e) = message_result {
+                nih_error!("Failed to process NoteEvent {:?}", e);
+            }
+            //We never produce sound so the voice is done as soon as its note ends, this lets
+            //the host stop sending poly modulation for it
+            if let NoteEvent::NoteOff {
+                timing,
+                voice_id,
+                channel,
+                note,
+                velocity: _,
+            } = event
+            {
+                context.send_event(NoteEvent::VoiceTerminated {
+                    timing,
+                    voice_id,
+                    channel,
+                    note,
+                });
+            }
+        }
+        if let Err(e) = self.process_expressions(context) {
+            nih_error!("Failed to send note expressions {:?}", e);
+        }
+        let arp_result = self.process_arpeggiator(context.transport(), buffer.samples());
+        if let Err(e) = arp_result {
+            nih_error!("Failed to send arpeggiator notes {:?}", e);
+        }
+        //Process Audio Triggers, straight from the host buffer so they don't pay for resampling
+        if self.params.flag_send_onsets.value() {
+            self.process_onsets(buffer);
+        }
+        if self.params.flag_level_cc.value() {
+            self.process_level_cc(buffer, context);
+        }
+        //Process Audio Events
+        if self.params.flag_send_audio.value() {
+            let audio_result = self.process_audio_buffer(buffer, context);
+            if audio_result.is_err() {
+                nih_error!("Failed to process Audio {:?}", audio_result.unwrap_err());
+            }
+        }
+        self.sample_count += buffer.samples() as u64;
+        if let Some(process_start) = process_start {
+            self.process_timing.record(process_start.elapsed());
+        }
+        ProcessStatus::Normal
+    }
+}
+
+impl OsClap {
+    /// Every packet the worker sends is copied to `observer` as well, for embedding and tests
+    /// that want to see the output without a socket. `None` stops copying
+    pub fn set_packet_observer(&self, observer: Option<Sender<OscPacket>>) {
+        *self.packet_observer.write() = observer;
+    }
+
+    /// Only what changed since the last buffer is sent, while playing that is the position of
+    /// every buffer. What the host leaves out is skipped instead of sent as 0
+    fn process_transport(&mut self, transport: &Transport) -> Result<()> {
+        let current = OscTransportType {
+            tempo: transport.tempo,
+            playing: Some(transport.playing),
+            position: transport.pos_beats(),
+        };
+        let sent = self.sent_transport;
+        let changed = OscTransportType {
+            tempo: current.tempo.filter(|_| current.tempo != sent.tempo),
+            playing: current.playing.filter(|_| current.playing != sent.playing),
+            position: current.position.filter(|_| current.position != sent.position),
+        };
+        if changed == OscTransportType::default() {
+            return Ok(());
+        }
+        self.sent_transport = current;
+        self.realtime_sender.send(OscChannelMessageType::Transport(changed))
+    }
+
+    fn process_params(&mut self, context: &mut impl ProcessContext<Self>) -> Result<()> {
+        if !self.params.flag_bundle_params.value() {
+            for index in 0..NUM_PARAMS {
+                self.send_dirty_param(index, context, None)?;
+            }
+            return Ok(());
+        }
+        let mut batch = [None; NUM_PARAMS];
+        for index in 0..NUM_PARAMS {
+            self.send_dirty_param(index, context, Some(&mut batch))?;
+        }
+        //An empty bundle would only tell the receiver nothing changed
+        if batch.iter().any(Option::is_some) {
+            self.realtime_sender
+                .send(OscChannelMessageType::ParamBatch(OscParamBatchType {
+                    params: batch,
+                    time_offset_ms: self.params.osc_time_offset.value(),
+                }))?;
+        }
+        Ok(())
+    }
+
+    /// Swallow the changes the host makes while restoring state, the values are taken as sent
+    /// so nothing is caught up on once the grace period is over
+    fn skip_loaded_params(&mut self, num_samples: usize) {
+        self.load_grace_samples = self.load_grace_samples.saturating_sub(num_samples);
+        for dirty in self.param_dirty.iter() {
+            dirty.store(false, Ordering::Release);
+        }
+        for (index, sent_value) in self.sent_param_values.iter_mut().enumerate() {
+            *sent_value = self.params.step_param(index, num_samples as u32);
+        }
+        if self.load_grace_samples == 0 {
+            nih_trace!("Load grace period over, sending param changes");
+        }
+    }
+
+    /// The host splits the buffer at automation points, within each split the values are stepped
+    /// through at the OSC sample rate and every change is sent
+    fn process_params_sample_accurate(
+        &mut self,
+        num_samples: usize,
         context: &mut impl ProcessContext<Self>,
-    ) -> ProcessStatus {
-        //Process Dirty Params
-        let param_result = self.process_params();
-        if param_result.is_err() {
-            nih_error!("Failed to send params {:?}", param_result.unwrap_err());
+    ) -> Result<()> {
+        //The values are compared below, a dirty flag left set would send a stale value
+        //once sample accurate mode is turned off again
+        for dirty in self.param_dirty.iter() {
+            dirty.store(false, Ordering::Release);
+        }
+        let osc_sample_rate = self.params.osc_sample_rate.value().max(1) as f32;
+        let step_size = ((self.input_sample_rate / osc_sample_rate) as usize).max(1);
+        let mut position = 0;
+        while position < num_samples {
+            let steps = step_size.min(num_samples - position);
+            let timing = position as u32;
+            position += steps;
+            let held: [bool; NUM_PARAMS] = std::array::from_fn(|index| self.param_held(index));
+            for (index, sent_value) in self.sent_param_values.iter_mut().enumerate() {
+                let param = &self.params[index];
+                let value = self.params.step_param(index, steps as u32);
+                if value == *sent_value || held[index] {
+                    continue;
+                }
+                *sent_value = value;
+                let config = self.param_configs[index];
+                let route = config.route;
+                if route.midi {
+                    send_param_cc(context, route, param.preview_normalized(value), timing);
+                }
+                if !route.osc {
+                    continue;
+                }
+                let value = match config.send_mode {
+                    ParamSendMode::Toggle => match self.param_toggles[index].update(value) {
+                        Some(_) => self.param_toggles[index].value(),
+                        None => continue,
+                    },
+                    _ => {
+                        let value = osc_param_value(param, value, self.normalized_params);
+                        let format = self.params.param_value_format.value();
+                        format.output_value(value, config.bipolar)
+                    }
+                };
+                self.realtime_sender
+                    .send(OscChannelMessageType::Param(OscParamType {
+                        index,
+                        value,
+                        mode: config.send_mode,
+                        comparator: config.comparator,
+                        format: self.params.param_value_format.value(),
+                        slugify_name: self.params.flag_slugify_names.value(),
+                        with_previous: self.params.flag_send_previous.value(),
+                    }))?;
+                self.param_activity.mark(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Current value of every OSC routed param, changed or not
+    fn send_all_params(&self) -> Result<()> {
+        for index in 0..NUM_PARAMS {
+            let config = self.param_configs[index];
+            if !config.route.osc {
+                continue;
+            }
+            let param = &self.params[index];
+            let value = match config.send_mode {
+                ParamSendMode::Toggle => self.param_toggles[index].value(),
+                _ => {
+                    let value = osc_param_value(param, param.value(), self.normalized_params);
+                    let format = self.params.param_value_format.value();
+                    format.output_value(value, config.bipolar)
+                }
+            };
+            self.realtime_sender
+                .send(OscChannelMessageType::Param(OscParamType {
+                    index,
+                    value,
+                    mode: config.send_mode,
+                    comparator: config.comparator,
+                    format: self.params.param_value_format.value(),
+                    slugify_name: self.params.flag_slugify_names.value(),
+                    with_previous: self.params.flag_send_previous.value(),
+                }))?;
+            self.param_activity.mark(index);
+        }
+        Ok(())
+    }
+
+    /// Whether the param was sent more recently than its minimum interval allows, the change
+    /// is picked up again once the interval has passed
+    fn param_held(&self, index: usize) -> bool {
+        let min_interval = param_min_interval(
+            self.param_configs[index].min_interval_ms,
+            self.params.param_max_rate.value(),
+        );
+        !min_interval.is_zero()
+            && self
+                .param_activity
+                .since_sent(index)
+                .is_some_and(|since| since < min_interval)
+    }
+
+    /// Every param in one message, changed or not, for receivers that read a snapshot per frame
+    fn send_param_digest(&self) -> Result<()> {
+        let values = std::array::from_fn(|index| {
+            let param = &self.params[index];
+            let value = osc_param_value(param, param.value(), self.normalized_params);
+            let bipolar = self.param_configs[index].bipolar;
+            self.params.param_value_format.value().output_value(value, bipolar)
+        });
+        self.realtime_sender
+            .send(OscChannelMessageType::ParamDigest(OscParamDigestType {
+                values,
+                format: self.params.param_value_format.value(),
+                with_names: self.params.flag_digest_names.value(),
+                slugify_name: self.params.flag_slugify_names.value(),
+            }))?;
+        for index in 0..NUM_PARAMS {
+            self.param_activity.mark(index);
+        }
+        Ok(())
+    }
+
+    /// With a `batch` the message is added to it instead of being sent on its own
+    fn send_dirty_param(
+        &mut self,
+        index: usize,
+        context: &mut impl ProcessContext<Self>,
+        batch: Option<&mut [Option<OscParamType>; NUM_PARAMS]>,
+    ) -> Result<()> {
+        //Left dirty so the latest value goes out once the interval is over
+        if self.param_configs[index].route.osc && self.param_held(index) {
+            return Ok(());
+        }
+        if self.param_dirty[index]
+            .compare_exchange(true, false, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            let param = &self.params[index];
+            nih_trace!("Param Dirty: {} {}", param.name(), param.value());
+            let config = self.param_configs[index];
+            let route = config.route;
+            if route.midi {
+                send_param_cc(context, route, param.modulated_normalized_value(), 0);
+            }
+            //The latch follows every change, the clock sends whatever state it is in
+            let toggled = match config.send_mode {
+                ParamSendMode::Toggle => self.param_toggles[index].update(param.value()),
+                _ => None,
+            };
+            //The digest and the clock send OSC on their own schedule
+            let scheduled = self.params.flag_param_digest.value()
+                || self.params.clock_division.value() != ClockDivision::Off;
+            if !route.osc || scheduled {
+                return Ok(());
+            }
+            let value = match config.send_mode {
+                ParamSendMode::Toggle if toggled.is_none() => return Ok(()),
+                ParamSendMode::Toggle => self.param_toggles[index].value(),
+                _ => {
+                    let value = osc_param_value(param, param.value(), self.normalized_params);
+                    let format = self.params.param_value_format.value();
+                    format.output_value(value, config.bipolar)
+                }
+            };
+            let message = OscParamType {
+                index,
+                value,
+                mode: config.send_mode,
+                comparator: config.comparator,
+                format: self.params.param_value_format.value(),
+                slugify_name: self.params.flag_slugify_names.value(),
+                with_previous: self.params.flag_send_previous.value(),
+            };
+            match batch {
+                Some(batch) => batch[index] = Some(message),
+                None => self.realtime_sender.send(OscChannelMessageType::Param(message))?,
+            }
+            self.param_activity.mark(index);
+        }
+        Ok(())
+    }
+
+    fn process_event(&mut self, event: &NoteEvent<()>, send_midi: bool) -> Result<()> {
+        match *event {
+            NoteEvent::PolyModulation {
+                timing: _,
+                voice_id,
+                poly_modulation_id,
+                normalized_offset,
+            } => {
+                //Poly modulation ids are the param indices
+                let index = poly_modulation_id as usize;
+                if let Some(param) = self.params.get(index) {
+                    let normalized = (param.unmodulated_normalized_value() + normalized_offset)
+                        .clamp(0.0, 1.0);
+                    self.realtime_sender.send(OscChannelMessageType::ParamModulation(
+                        OscParamModulationType {
+                            index,
+                            voice_id,
+                            value: param.preview_plain(normalized),
+                            slugify_name: self.params.flag_slugify_names.value(),
+                        },
+                    ))?
+                }
+            }
+            _ if !send_midi => {}
+            NoteEvent::NoteOn { note, .. }
+            | NoteEvent::NoteOff { note, .. }
+            | NoteEvent::PolyPressure { note, .. }
+                if !self.note_in_range(note) => {}
+            NoteEvent::NoteOn {
+                timing: _,
+                channel,
+                note,
+                velocity,
+                voice_id: _,
+            } => {
+                let note = ArpNote {
+                    channel,
+                    note,
+                    velocity,
+                };
+                if self.params.arp_rate.value() == ClockDivision::Off {
+                    self.send_note_on(note)?
+                } else {
+                    self.arpeggiator.note_on(note)
+                }
+            }
+            NoteEvent::NoteOff {
+                timing: _,
+                channel,
+                note,
+                velocity,
+                voice_id: _,
+            } => {
+                //Notes the arpeggiator holds were never sent, only the pattern is
+                let held = self.arpeggiator.note_off(channel, note);
+                if !held {
+                    self.send_note_off(ArpNote {
+                        channel,
+                        note,
+                        velocity,
+                    })?
+                }
+            }
+            NoteEvent::MidiCC {
+                timing: _,
+                channel,
+                cc,
+                value,
+            } => {
+                let mut named = false;
+                for (named_cc, name) in self.named_ccs.iter().zip(NAMED_CC_NAMES) {
+                    if named_cc.enabled && named_cc.cc == cc {
+                        named = true;
+                        self.realtime_sender
+                            .send(OscChannelMessageType::NamedCc(OscNamedCcType {
+                                name,
+                                channel,
+                                value,
+                                format: self.midi_value_format(),
+                            }))?;
+                    }
+                }
+                //A CC with a name of its own isn't sent twice
+                if !named {
+                    self.realtime_sender.send(OscChannelMessageType::Cc(OscMidiValueType {
+                        channel,
+                        number: Some(cc),
+                        value,
+                        format: self.midi_value_format(),
+                    }))?;
+                }
+            }
+            NoteEvent::MidiPitchBend {
+                timing: _,
+                channel,
+                value,
+            } => {
+                let format = self.midi_value_format();
+                self.realtime_sender.send(OscChannelMessageType::PitchBend(OscMidiValueType {
+                    channel,
+                    number: None,
+                    //Centered like bipolar params
+                    value: format.output_value(value, true),
+                    format,
+                }))?
+            }
+            NoteEvent::MidiChannelPressure {
+                timing: _,
+                channel,
+                pressure,
+            } => self
+                .realtime_sender
+                .send(OscChannelMessageType::Aftertouch(OscMidiValueType {
+                    channel,
+                    number: None,
+                    value: pressure,
+                    format: self.midi_value_format(),
+                }))?,
+            NoteEvent::PolyPressure {
+                timing: _,
+                voice_id: _,
+                channel,
+                note,
+                pressure,
+            } => self
+                .realtime_sender
+                .send(OscChannelMessageType::Aftertouch(OscMidiValueType {
+                    channel,
+                    number: Some(note),
+                    value: pressure,
+                    format: self.midi_value_format(),
+                }))?,
+            _ => {}
+        };
+        Ok(())
+    }
+
+    /// Keeps track of the held notes, a new one gets every note expression straight away so it
+    /// doesn't start from the host's default
+    fn track_voice(
+        &mut self,
+        event: &NoteEvent<()>,
+        context: &mut impl ProcessContext<Self>,
+    ) -> Result<()> {
+        match *event {
+            NoteEvent::NoteOn {
+                timing,
+                voice_id,
+                channel,
+                note,
+                velocity: _,
+            } => {
+                let voice = Voice {
+                    voice_id,
+                    channel,
+                    note,
+                };
+                self.voices.start(voice);
+                for index in 0..NUM_PARAMS {
+                    self.send_expression(index, timing, voice, context)?;
+                }
+            }
+            NoteEvent::NoteOff {
+                voice_id,
+                channel,
+                note,
+                ..
+            }
+            | NoteEvent::Choke {
+                voice_id,
+                channel,
+                note,
+                ..
+            } => self.voices.end(voice_id, channel, note),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Params sent as note expressions go out to every held note whenever they change
+    fn process_expressions(&mut self, context: &mut impl ProcessContext<Self>) -> Result<()> {
+        for index in 0..NUM_PARAMS {
+            if self.param_configs[index].expression == ParamExpression::Off {
+                continue;
+            }
+            let value = self.params[index].value();
+            if value == self.expression_values[index] {
+                continue;
+            }
+            self.expression_values[index] = value;
+            for voice in self.voices.iter() {
+                self.send_expression(index, 0, *voice, context)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The note expression of one param for one voice, and the same value over OSC
+    fn send_expression(
+        &self,
+        index: usize,
+        timing: u32,
+        voice: Voice,
+        context: &mut impl ProcessContext<Self>,
+    ) -> Result<()> {
+        let config = self.param_configs[index];
+        let param = &self.params[index];
+        let Some(event) = config.expression.event(timing, voice, param.value()) else {
+            return Ok(());
+        };
+        context.send_event(event);
+        if config.route.osc {
+            self.realtime_sender
+                .send(OscChannelMessageType::ParamExpression(OscParamExpressionType {
+                    index,
+                    voice_id: voice.voice_id.unwrap_or(-1),
+                    channel: voice.channel,
+                    note: voice.note,
+                    value: param.value(),
+                    slugify_name: self.params.flag_slugify_names.value(),
+                }))?;
+        }
+        Ok(())
+    }
+
+    /// CCs and aftertouch follow `Param Format` unless they are forced to MIDI ints
+    fn midi_value_format(&self) -> ParamValueFormat {
+        if self.params.flag_midi_int_values.value() {
+            ParamValueFormat::Midi
+        } else {
+            self.params.param_value_format.value()
+        }
+    }
+
+    fn send_note_on(&self, note: ArpNote) -> Result<()> {
+        self.realtime_sender.send(OscChannelMessageType::NoteOn(OscNoteType {
+            channel: note.channel,
+            note: note.note,
+            velocity: note.velocity,
+            int_velocity: self.params.flag_midi_int_values.value(),
+            split: self.params.flag_split_notes.value(),
+            trigger: self.params.flag_note_trigger.value(),
+            time_offset_ms: self.params.osc_time_offset.value(),
+        }))?;
+        Ok(())
+    }
+
+    fn send_note_off(&self, note: ArpNote) -> Result<()> {
+        self.realtime_sender.send(OscChannelMessageType::NoteOff(OscNoteType {
+            channel: note.channel,
+            note: note.note,
+            velocity: note.velocity,
+            int_velocity: self.params.flag_midi_int_values.value(),
+            split: self.params.flag_split_notes.value(),
+            trigger: false,
+            time_offset_ms: self.params.osc_time_offset.value(),
+        }))?;
+        Ok(())
+    }
+
+    /// Plays the held notes on the host's beat grid, the sounding note is stopped when the host
+    /// stops and everything is let go when the arpeggiator is turned off
+    fn process_arpeggiator(&mut self, transport: &Transport, num_samples: usize) -> Result<()> {
+        let Some(ticks_per_beat) = self.params.arp_rate.value().ticks_per_beat() else {
+            if let Some(note) = self.arpeggiator.clear() {
+                self.send_note_off(note)?;
+            }
+            return Ok(());
+        };
+        let ticked = self.arp_clock.ticked(
+            transport,
+            num_samples,
+            self.input_sample_rate,
+            ticks_per_beat,
+        );
+        if !self.arp_clock.is_running() {
+            if let Some(note) = self.arpeggiator.stop() {
+                self.send_note_off(note)?;
+            }
+            return Ok(());
+        }
+        if !ticked {
+            return Ok(());
+        }
+        let octaves = self.params.arp_octaves.value() as usize;
+        let (off, on) = self.arpeggiator.tick(self.params.arp_pattern.value(), octaves);
+        if let Some(note) = off {
+            self.send_note_off(note)?;
+        }
+        if let Some(note) = on {
+            self.send_note_on(note)?;
+        }
+        Ok(())
+    }
+
+    /// A note on without velocity is a note off by MIDI convention, some hosts and controllers
+    /// pass those through as note ons
+    fn zero_velocity_note_off(&self, event: NoteEvent<()>) -> NoteEvent<()> {
+        match event {
+            NoteEvent::NoteOn {
+                timing,
+                voice_id,
+                channel,
+                note,
+                velocity,
+            } if velocity == 0.0 && self.params.flag_zero_velocity_off.value() => {
+                NoteEvent::NoteOff {
+                    timing,
+                    voice_id,
+                    channel,
+                    note,
+                    velocity,
+                }
+            }
+            event => event,
+        }
+    }
+
+    /// The range is inclusive and works either way around
+    fn note_in_range(&self, note: u8) -> bool {
+        let low = self.params.note_range_low.value();
+        let high = self.params.note_range_high.value();
+        (low.min(high)..=low.max(high)).contains(&(note as i32))
+    }
+
+    fn process_audio_buffer(
+        &mut self,
+        buffer: &mut Buffer,
+        context: &mut impl ProcessContext<Self>,
+    ) -> Result<()> {
+        if self.params.flag_send_zcr.value() {
+            //Crossings only mean something at the input rate, the resampled signal is too coarse
+            let channels = self
+                .params
+                .analysis_channel
+                .value()
+                .select(buffer.as_slice_immutable());
+            let rate = zero_crossing_rate(channels, self.input_sample_rate);
+            self.realtime_sender
+                .send(OscChannelMessageType::AudioZcr(OscAudioType { value: rate }))?;
+        }
+        if self.params.audio_mode.value() == AudioMode::Meter {
+            let per_channel = self.params.flag_meter_per_channel.value();
+            let meter = audio_meter(buffer.as_slice_immutable(), per_channel);
+            return self.realtime_sender.send(OscChannelMessageType::AudioMeter(meter));
+        }
+        if self.params.osc_sample_rate_dirty.swap(false, Ordering::AcqRel) {
+            //Built on the background thread, the old resampler keeps going until it is there
+            nih_trace!("OSC sample rate changed to {}", self.params.osc_sample_rate.value());
+            context.execute_background(OsClapTask::BuildResampler(self.resampler_settings()));
+        }
+        self.swap_resampler()?;
+        if let Some(AudioResampler {
+            resampler,
+            input: queue,
+            output,
+        }) = &mut self.resampler
+        {
+            if resampler.nbr_channels() == 1 && buffer.channels() > 1 {
+                //Fallback from initialize, the mono resampler gets the sum
+                queue_mono(&mut queue[0], buffer.as_slice_immutable());
+            } else if queue.len() == buffer.channels() {
+                for (queued, channel) in queue.iter_mut().zip(buffer.as_slice_immutable()) {
+                    queue_samples(queued, channel);
+                }
+            } else {
+                anyhow::bail!(
+                    "Resampler expects {} channels, got {}",
+                    queue.len(),
+                    buffer.channels()
+                );
+            }
+            //Every complete chunk is resampled, the rest waits for the next buffer
+            while queue[0].len() >= resampler.input_frames_next() {
+                let time = self.resampler_input_start as f64 / self.input_sample_rate as f64;
+                let (input_frames, output_frames) = resample_chunk(resampler, queue, output)?;
+                self.resampler_input_start += input_frames as u64;
+                if self.params.flag_audio_blob.value() {
+                    send_audio_block(&self.realtime_sender, &self.audio_blocks, time, output)?;
+                    continue;
+                }
+                send_audio_frames(
+                    &self.realtime_sender,
+                    output,
+                    output_frames,
+                    self.params.flag_skip_silence.value(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes a resampler the background thread built for the current settings, the one it
+    /// replaces goes back to be dropped there. Doesn't wait for the lock, a later buffer swaps
+    fn swap_resampler(&mut self) -> Result<()> {
+        let current = self.resampler_settings();
+        let Some(mut swap) = self.resampler_swap.try_lock() else {
+            return Ok(());
+        };
+        //Built for settings that changed since, a build for the new ones replaces it
+        if !matches!(&swap.built, Some((settings, _)) if *settings == current) {
+            return Ok(());
+        }
+        let Some((_, built)) = swap.built.take() else {
+            return Ok(());
+        };
+        //Input left for the old resampler is dropped, the block times skip over it
+        if let Some(queued) = self.resampler.as_ref().and_then(|old| old.input.first()) {
+            self.resampler_input_start += queued.len() as u64;
+        }
+        //Empty since every build clears it before leaving a new one
+        swap.retired = std::mem::replace(&mut self.resampler, built);
+        drop(swap);
+        self.realtime_sender.send(OscChannelMessageType::Info(self.info()))
+    }
+
+    fn process_onsets(&mut self, buffer: &mut Buffer) {
+        if let Some(onset_detector) = &mut self.onset_detector {
+            let settings = OnsetSettings {
+                low_edge: self.params.onset_low_edge.value(),
+                high_edge: self.params.onset_high_edge.value(),
+                sensitivity: [
+                    self.params.onset_low_sensitivity.value(),
+                    self.params.onset_mid_sensitivity.value(),
+                    self.params.onset_high_sensitivity.value(),
+                ],
+            };
+            let channels = self.params.analysis_channel.value().select(buffer.as_slice());
+            onset_detector.process(channels, &settings, |band, strength| {
+                let send_result = self
+                    .realtime_sender
+                    .send(OscChannelMessageType::Onset(OscOnsetType { band, strength }));
+                if let Err(e) = send_result {
+                    nih_error!("Failed to send onset {:?}", e);
+                }
+            });
+        }
+    }
+
+    /// The DAW gets the input level as a CC it can map to anything on other tracks
+    fn process_level_cc(&mut self, buffer: &mut Buffer, context: &mut impl ProcessContext<Self>) {
+        let rate = self.params.level_cc_rate.value().max(1) as f32;
+        let window_samples = (self.input_sample_rate / rate) as usize;
+        let cc = self.params.level_cc.value() as u8;
+        let channel = (self.params.level_cc_channel.value() - 1) as u8;
+        let channels = self.params.analysis_channel.value().select(buffer.as_slice());
+        let mapping = self.params.level_mapping.value();
+        self.level_follower
+            .process(channels, window_samples, mapping, |timing, level| {
+                context.send_event(NoteEvent::MidiCC {
+                    timing,
+                    channel,
+                    cc,
+                    value: level,
+                });
+            });
+    }
+
+    fn resampler_settings(&self) -> ResamplerSettings {
+        ResamplerSettings {
+            input_sample_rate: self.input_sample_rate,
+            osc_sample_rate: self.params.osc_sample_rate.value(),
+            channels: self.input_channels,
+            max_buffer_size: self.max_buffer_size,
+        }
+    }
+
+    fn info(&self) -> OscInfoType {
+        OscInfoType {
+            sample_rate: self.input_sample_rate,
+            osc_sample_rate: self.params.osc_sample_rate.value(),
+        }
+    }
+
+    /// Hands the persisted settings to a worker that is already running
+    fn push_settings(&self) {
+        //Info goes first so the reconnect below announces the new rates
+        if let Err(e) = self.sender.send(OscChannelMessageType::Info(self.info())) {
+            nih_error!("Failed to send Info update {:?}", e);
+        }
+        let connection_send_result = {
+            let _guard = self.params.connection_guard.lock();
+            self.sender
+                .send(OscChannelMessageType::ConnectionChange(self.params.connection()))
+        };
+        if connection_send_result.is_err() {
+            nih_error!(
+                "Failed to send ConnectionChange update {:?}",
+                connection_send_result.unwrap_err()
+            );
+        }
+        let categories = *self.params.osc_categories.read();
+        if let Err(e) = self.sender.send(OscChannelMessageType::CategoriesChange(categories)) {
+            nih_error!("Failed to send CategoriesChange update {:?}", e);
+        }
+        let address_base = self.params.osc_address_base.read().to_string();
+        nih_trace!("OSC Address Base: {}", address_base);
+        let address_send_result = self.sender.send(OscChannelMessageType::AddressBaseChange(
+            OscAddressBaseType {
+                address: address_base,
+            },
+        ));
+        if address_send_result.is_err() {
+            nih_error!(
+                "Failed to send AddressBaseChange update {:?}",
+                address_send_result.unwrap_err()
+            );
+        }
+        let param_addresses = self.params.param_addresses.read().clone();
+        let param_addresses_send_result = self.sender.send(
+            OscChannelMessageType::ParamAddressesChange(OscParamAddressesType {
+                addresses: param_addresses,
+            }),
+        );
+        if let Err(e) = param_addresses_send_result {
+            nih_error!("Failed to send ParamAddressesChange update {:?}", e);
+        }
+    }
+
+    /// Stops the worker and waits for it, whatever it left in the channel is dropped so a
+    /// stale Exit can't stop the next worker straight away. Neither a full channel nor a stuck
+    /// worker can hold this up for longer than `WORKER_STOP_TIMEOUT`, and editor messages put
+    /// back for the next worker never wait for room
+    fn kill_background_thread(&mut self) {
+        let osc_thread = match self.osc_thread.take() {
+            Some(osc_thread) => osc_thread,
+            None => return,
+        };
+        //A full channel still wakes the worker up, it sees the flag with the next message
+        self.osc_thread_stop.store(true, Ordering::Release);
+        if let Err(e) = self.sender.try_send(OscChannelMessageType::Exit) {
+            nih_trace!("Background thread is stopped by flag, Exit not queued {:?}", e);
+        }
+        let stopped = matches!(
+            self.osc_thread_done.recv_timeout(WORKER_STOP_TIMEOUT),
+            Err(RecvTimeoutError::Disconnected)
+        );
+        if !stopped {
+            nih_error!("Background thread didn't stop in time, leaving it behind");
+        } else if osc_thread.join().is_err() {
+            nih_error!("Background thread panicked");
+        }
+        //Streamed data and rates are stale by the time a new worker starts, but what the editor
+        //sent in the meantime (custom messages, connection edits) is put back for the next worker
+        let pending: Vec<_> = self
+            .receiver
+            .try_iter()
+            .filter(|message| {
+                message.category().is_none()
+                    && !matches!(
+                        message,
+                        OscChannelMessageType::Exit | OscChannelMessageType::Info(_)
+                    )
+            })
+            .collect();
+        for message in pending {
+            if let Err(e) = self.sender.try_send(message) {
+                nih_error!("Failed to keep editor message for the next worker {:?}", e);
+                self.realtime_sender.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Before `param_configs` every per-param setting was persisted as its own array, those are
+/// folded into the configs so older projects keep their settings
+fn migrate_param_configs(state: &mut PluginState) {
+    if state.fields.contains_key("param_configs") {
+        return;
+    }
+    fn legacy<T: serde::de::DeserializeOwned>(state: &mut PluginState, key: &str) -> Option<T> {
+        let json = state.fields.remove(key)?;
+        match serde_json::from_str(&json) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                nih_error!("Dropping unreadable {} {:?}", key, e);
+                None
+            }
+        }
+    }
+    //Always 8 long, params past that keep their defaults
+    let send_modes: Option<Vec<ParamSendMode>> = legacy(state, "param_send_modes");
+    let bipolar: Option<Vec<bool>> = legacy(state, "param_bipolar");
+    let routes: Option<Vec<ParamRoute>> = legacy(state, "param_routes");
+    let comparators: Option<Vec<ParamComparator>> = legacy(state, "param_comparators");
+    if send_modes.is_none() && bipolar.is_none() && routes.is_none() && comparators.is_none() {
+        return;
+    }
+    let configs: [ParamConfig; NUM_PARAMS] = std::array::from_fn(|index| {
+        let mut config = ParamConfig::new(index);
+        if let Some(send_modes) = send_modes.as_ref().and_then(|send_modes| send_modes.get(index)) {
+            config.send_mode = *send_modes;
+        }
+        if let Some(bipolar) = bipolar.as_ref().and_then(|bipolar| bipolar.get(index)) {
+            config.bipolar = *bipolar;
+        }
+        if let Some(routes) = routes.as_ref().and_then(|routes| routes.get(index)) {
+            config.route = *routes;
         }
-        //Process Note Events
-        if self.params.flag_send_midi.value() {
-            while let Some(event) = context.next_event() {
-                nih_trace!("NoteEvent: {:?}", event);
-                let message_result = self.process_event(&event);
-                if message_result.is_err() {
+        if let Some(comparators) = comparators.as_ref().and_then(|comparators| comparators.get(index)) {
+            config.comparator = *comparators;
+        }
+        config
+    });
+    match serde_json::to_string(&configs) {
+        Ok(json) => {
+            nih_trace!("Migrated per-param settings to param_configs");
+            state.fields.insert("param_configs".to_string(), json);
+        }
+        Err(e) => nih_error!("Failed to migrate per-param settings {:?}", e),
+    }
+}
+
+fn send_param_cc(
+    context: &mut impl ProcessContext<OsClap>,
+    route: ParamRoute,
+    normalized: f32,
+    timing: u32,
+) {
+    context.send_event(NoteEvent::MidiCC {
+        timing,
+        channel: 0,
+        cc: route.cc,
+        value: normalized,
+    });
+}
+
+/// Sign changes per second of the channels mixed down, for a plain sine that is twice its
+/// frequency
+fn zero_crossing_rate(channels: &[&mut [f32]], sample_rate: f32) -> f32 {
+    let num_samples = channels.first().map_or(0, |channel| channel.len());
+    if num_samples < 2 {
+        return 0.0;
+    }
+    let mix = |i: usize| channels.iter().map(|channel| channel[i]).sum::<f32>();
+    let crossings = (1..num_samples)
+        .filter(|&i| (mix(i - 1) >= 0.0) != (mix(i) >= 0.0))
+        .count();
+    crossings as f32 * sample_rate / num_samples as f32
+}
+
+/// The plain value, or with `normalized` where it sits in the param's range from 0 to 1. The
+/// two are the same for a 0-1 param, they only differ once a param has another range
+fn osc_param_value(param: &FloatParam, plain: f32, normalized: bool) -> f32 {
+    if normalized {
+        param.preview_normalized(plain)
+    } else {
+        plain
+    }
+}
+
+/// L,R,L,R... from one buffer per channel, `interleaved` is cleared first
+fn interleave(channels: &[Vec<f32>], interleaved: &mut Vec<f32>) {
+    let frames = channels.iter().map(Vec::len).min().unwrap_or(0);
+    interleaved.clear();
+    interleaved
+        .extend((0..frames).flat_map(|frame| channels.iter().map(move |channel| channel[frame])));
+}
+
+/// Average of all channels, `mono` sets how many samples are mixed
+fn mix_to_mono(channels: &[&mut [f32]], mono: &mut [f32]) {
+    mono.fill(0.0);
+    for channel in channels {
+        for (mixed, sample) in mono.iter_mut().zip(channel.iter()) {
+            *mixed += sample;
+        }
+    }
+    let scale = 1.0 / channels.len().max(1) as f32;
+    for mixed in mono.iter_mut() {
+        *mixed *= scale;
+    }
+}
+
+/// Appends to a resampler queue, never past what it has room for so the audio thread doesn't
+/// allocate
+fn queue_samples(queue: &mut Vec<f32>, samples: &[f32]) {
+    let room = queue.capacity() - queue.len();
+    queue.extend_from_slice(&samples[..samples.len().min(room)]);
+}
+
+/// Peak and RMS of each channel up to `MAX_AUDIO_CHANNELS`, or of all of them together
+fn audio_meter(channels: &[&mut [f32]], per_channel: bool) -> OscAudioMeterType {
+    let mut meter = OscAudioMeterType {
+        peak: [0.0; MAX_AUDIO_CHANNELS],
+        rms: [0.0; MAX_AUDIO_CHANNELS],
+        channels: 1,
+    };
+    let channels = &channels[..channels.len().min(MAX_AUDIO_CHANNELS)];
+    let mut squares = [0.0; MAX_AUDIO_CHANNELS];
+    let mut samples = [0; MAX_AUDIO_CHANNELS];
+    for (index, channel) in channels.iter().enumerate() {
+        //Everything adds up in the first level when it is across all channels
+        let index = if per_channel { index } else { 0 };
+        for &sample in channel.iter() {
+            meter.peak[index] = meter.peak[index].max(sample.abs());
+            squares[index] += sample * sample;
+        }
+        samples[index] += channel.len();
+    }
+    if per_channel {
+        meter.channels = channels.len().max(1);
+    }
+    for ((rms, squares), samples) in meter.rms.iter_mut().zip(squares).zip(samples) {
+        if samples > 0 {
+            *rms = (squares / samples as f32).sqrt();
+        }
+    }
+    meter
+}
+
+/// Appends the sum of the channels to a mono resampler queue
+fn queue_mono(queue: &mut Vec<f32>, channels: &[&mut [f32]]) {
+    let start = queue.len();
+    let samples = channels.first().map_or(0, |channel| channel.len());
+    let end = (start + samples).min(queue.capacity());
+    queue.resize(end, 0.0);
+    mix_to_mono(channels, &mut queue[start..]);
+}
+
+/// One Audio message per resampled frame, with `skip_silence` frames where every channel is
+/// exactly zero are left out
+fn send_audio_frames(
+    sender: &RealtimeSender,
+    resampled: &[Vec<f32>],
+    frames: usize,
+    skip_silence: bool,
+) {
+    let channels = resampled.len().min(MAX_AUDIO_CHANNELS);
+    for frame in 0..frames {
+        let mut samples = [0.0; MAX_AUDIO_CHANNELS];
+        for (sample, channel) in samples.iter_mut().zip(resampled.iter()) {
+            *sample = channel[frame];
+        }
+        if skip_silence && samples.iter().all(|&sample| sample == 0.0) {
+            continue;
+        }
+        let send_result =
+            sender.send(OscChannelMessageType::Audio(OscAudioFrameType { samples, channels }));
+        if send_result.is_err() {
+            nih_error!("Failed to send processed audio {:?}", send_result.unwrap_err());
+            break;
+        }
+    }
+}
+
+/// One AudioBlock message with a buffer from the pool, counted as dropped without a free one
+fn send_audio_block(
+    sender: &RealtimeSender,
+    pool: &AudioBlockPool,
+    time: f64,
+    resampled: &[Vec<f32>],
+) -> Result<()> {
+    let Ok(mut samples) = pool.receiver.try_recv() else {
+        sender.dropped.fetch_add(1, Ordering::Relaxed);
+        return Ok(());
+    };
+    let channels = resampled.len().min(MAX_AUDIO_CHANNELS);
+    interleave(&resampled[..channels], &mut samples);
+    sender.send(OscChannelMessageType::AudioBlock(OscAudioBlockType {
+        time,
+        channels,
+        samples,
+        pool: pool.sender.clone(),
+    }))
+}
+
+/// Work the audio thread hands to the background thread since it would allocate
+pub enum OsClapTask {
+    BuildResampler(ResamplerSettings),
+}
+
+/// What a resampler is built for
+#[derive(Clone, Copy, PartialEq)]
+pub struct ResamplerSettings {
+    input_sample_rate: f32,
+    osc_sample_rate: i32,
+    /// Of the host's input, the resampler gets as many
+    channels: usize,
+    max_buffer_size: usize,
+}
+
+/// A resampler with the buffers sized for it, swapped in as a whole
+struct AudioResampler {
+    resampler: FftFixedOut<f32>,
+    /// Input per resampler channel that doesn't fill a resampler chunk yet, allocated with the
+    /// resampler so it never has to grow
+    input: Vec<Vec<f32>>,
+    output: Vec<Vec<f32>>,
+}
+
+/// Resamplers are built and dropped on the background thread, the audio thread only swaps them
+#[derive(Default)]
+struct ResamplerSwap {
+    /// The latest build, `None` inside when no resampler could be built for the settings
+    built: Option<(ResamplerSettings, Option<AudioResampler>)>,
+    /// The resampler the last build replaced, dropped by the next build
+    retired: Option<AudioResampler>,
+}
+
+/// As many channels as the input when possible, otherwise mono with the input summed and
+/// without either audio processing is disabled
+fn build_resampler(settings: ResamplerSettings) -> Option<AudioResampler> {
+    let resampler = match create_resampler(settings, settings.channels) {
+        Ok(sampler) => sampler,
+        Err(e) if settings.channels > 1 => {
+            nih_error!("Failed to create stereo resampler, falling back to mono {:?}", e);
+            match create_resampler(settings, 1) {
+                Ok(sampler) => {
+                    nih_log!("Audio is summed to mono before resampling");
+                    sampler
+                }
+                Err(e) => {
                     nih_error!(
-                        "Failed to process NoteEvent {:?}",
-                        message_result.unwrap_err()
+                        "Failed to create resampler, audio processing will be disabled {:?}",
+                        e
                     );
+                    return None;
                 }
             }
         }
-        //Process Audio Events
-        if self.params.flag_send_audio.value() {
-            let audio_result = self.process_audio_buffer(buffer);
-            if audio_result.is_err() {
-                nih_error!("Failed to process Audio {:?}", audio_result.unwrap_err());
+        Err(e) => {
+            nih_error!("Failed to create resampler, audio processing will be disabled {:?}", e);
+            return None;
+        }
+    };
+    //Room for a chunk that is almost complete and the next host buffer
+    let queue_size = resampler.input_frames_max() + settings.max_buffer_size;
+    Some(AudioResampler {
+        input: (0..resampler.nbr_channels()).map(|_| Vec::with_capacity(queue_size)).collect(),
+        output: resampler.output_buffer_allocate(true),
+        resampler,
+    })
+}
+
+fn create_resampler(
+    settings: ResamplerSettings,
+    channels: usize,
+) -> Result<FftFixedOut<f32>, rubato::ResamplerConstructionError> {
+    FftFixedOut::<f32>::new(
+        settings.input_sample_rate.round() as usize,
+        settings.osc_sample_rate.max(0) as usize,
+        AUDIO_BLOCK_FRAMES,
+        2,
+        channels,
+    )
+}
+
+/// Resamples the chunk at the front of the queue and takes it off, returns the input and output
+/// frames. Rubato checks the buffer shapes itself and returns an error for a wrong one, which
+/// leaves the queue as it was
+fn resample_chunk(
+    resampler: &mut impl Resampler<f32>,
+    queue: &mut [Vec<f32>],
+    output: &mut [Vec<f32>],
+) -> Result<(usize, usize)> {
+    let (input_frames, output_frames) = resampler.process_into_buffer(queue, output, None)?;
+    for queued in queue.iter_mut() {
+        queued.drain(..input_frames);
+    }
+    Ok((input_frames, output_frames))
+}
+
+// /<osc_address_base>/info <version> <sample_rate> <osc_sample_rate>
+// /<osc_address_base>/meta/param/<param_name> <min> <max> <default> <value>
+// /<osc_address_base>/hello <version> -> /<osc_address_base>/hello_ack <feature>...
+// /<osc_address_base>/param/<param_name> <value>, the plain value or 0-1 over the param's range
+//   with normalized params, the same for the current 0-1 params
+// /<osc_address_base>/param/<param_name> <value> <previous> with previous values
+// /<osc_address_base>/param/<param_name>/relative <delta>
+// /<osc_address_base>/param/<param_name> <rising> in comparator mode
+// /<osc_address_base>/param/<param_name> <1|0> in toggle mode
+// /<osc_address_base>/param/<param_name>/mod <voice_id> <value>
+// /<osc_address_base>/param/<param_name>/expression <voice_id> <channel> <note> <value>
+// #bundle [/<osc_address_base>/param/<param_name> <value>]... with bundled params
+// /<osc_address_base>/params <param1> ... <param8> in digest mode
+// /<osc_address_base>/params <name1> <param1> ... <name8> <param8> in digest mode with names
+// /<osc_address_base>/note_on <channel> <note> <velocity>
+// /<osc_address_base>/note/pitch <note> + /<osc_address_base>/note/velocity <velocity>
+// /<osc_address_base>/trigger <impulse> with note triggers
+// /<osc_address_base>/note_off <channel> <note> <velocity>
+// /<osc_address_base>/all_notes_off after a note_off for every channel and note
+// /<osc_address_base>/<modwheel|breath|expression> <channel> <value>
+// /<osc_address_base>/cc <channel> <cc> <value>
+// /<osc_address_base>/pitchbend <channel> <value>
+// /<osc_address_base>/aftertouch <channel> <value>
+// /<osc_address_base>/aftertouch/poly <channel> <note> <value>
+// /<osc_address_base>/audio <sample>... with one sample per channel
+// /<osc_address_base>/audio/block <time> <channels> <interleaved_samples> with audio blobs
+// /<osc_address_base>/audio/peak <level>... with the meter audio mode
+// /<osc_address_base>/audio/rms <level>... with the meter audio mode
+// /<osc_address_base>/audio/zcr <crossings_per_second>
+// /<osc_address_base>/transport/playing <playing>
+// /<osc_address_base>/transport/bpm <bpm>
+// /<osc_address_base>/transport/position <beats>
+// /<osc_address_base>/samples <sample_count>
+// /<osc_address_base>/onset/<low|mid|high> <strength>
+// /<osc_address_base>/<custom_address> or /<custom_address> <custom_args>
+// /<osc_address_base>/repeat <count> in place of a packet identical to the last one
+// /<osc_address_base>/heartbeat <count> every heartbeat interval
+// /<osc_address_base>/bye when the plugin stops, with heartbeats
+
+/// How often the worker checks for a hello_ack while it is waiting for one
+const HANDSHAKE_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How long the worker waits before connecting again after a failed connect, doubled after
+/// every failure up to the max
+const RECONNECT_DELAY_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(8);
+
+/// How often the worker reads received param values while receiving is on
+const INCOMING_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Received param values waiting for the editor
+const INCOMING_PARAMS_LIMIT: usize = 1024;
+
+/// Received messages waiting for the editor to show them
+const RECEIVED_MESSAGES_LIMIT: usize = 256;
+
+/// Packets kept while there is no connection, the oldest are dropped past this
+const PENDING_PACKETS_LIMIT: usize = 1024;
+
+/// What the worker reports back to the plugin and the editor
+struct WorkerStatus {
+    handshake_state: Arc<RwLock<HandshakeState>>,
+    /// Param values received from the destination, for the editor to apply
+    incoming: Sender<OscChannelMessageType>,
+    /// Every message received, for the editor to show
+    received: Sender<OscChannelMessageType>,
+    /// Whether the worker can send, and why not
+    connection_status: Arc<RwLock<ConnectionStatus>>,
+    /// Set when the worker should stop but its Exit didn't fit in the channel
+    stop: Arc<AtomicBool>,
+}
+
+/// The OSC timetag reserved for "as soon as it arrives"
+const OSC_TIME_IMMEDIATELY: OscTime = OscTime {
+    seconds: 0,
+    fractional: 1,
+};
+
+/// Seconds from the 1900 epoch OSC timetags count from to the unix epoch
+const NTP_UNIX_EPOCH_OFFSET: u64 = 2_208_988_800;
+
+/// Bundle timetag for now shifted by the offset, positive offsets delay the receiver and negative
+/// ones lead. Without an offset the bundle is for immediate use like before
+fn osc_time(offset_ms: i32) -> OscTime {
+    if offset_ms == 0 {
+        return OSC_TIME_IMMEDIATELY;
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let offset = Duration::from_millis(offset_ms.unsigned_abs() as u64);
+    let time = if offset_ms > 0 {
+        now + offset
+    } else {
+        now.saturating_sub(offset)
+    };
+    OscTime {
+        seconds: (time.as_secs() + NTP_UNIX_EPOCH_OFFSET) as u32,
+        fractional: (((time.subsec_nanos() as u64) << 32) / 1_000_000_000) as u32,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn osc_client_worker(
+    mut transport: ObservedTransport,
+    params: Arc<OsClapParams>,
+    param_address_base: String,
+    mut info: OscInfoType,
+    connection: OscConnectionType,
+    status: WorkerStatus,
+    mut categories: CategoryMask,
+    recv: Receiver<OscChannelMessageType>,
+) -> () {
+    let WorkerStatus {
+        handshake_state,
+        incoming,
+        received,
+        connection_status,
+        stop,
+    } = status;
+    nih_trace!("Background thread spawned!");
+    nih_trace!("Background thread OSC Address Base: {}", param_address_base);
+    let mut addresses = OscAddresses::new(&param_address_base);
+    let mut param_names = ParamNames::new(&params, &params.param_addresses.read());
+    //Nothing is connected until the first attempt below
+    let mut connected = false;
+    //Last value per param, relative sends are measured from it and it is cleared on connect
+    let mut param_values: HashMap<usize, f32> = HashMap::new();
+    //Last message per param and the ones that changed without a connection, to catch the
+    //receiver up once it is back
+    let mut last_params: HashMap<usize, OscParamType> = HashMap::new();
+    let mut changed_params: HashSet<usize> = HashSet::new();
+    //Last packet sent in full and how often it was repeated since, while compressing repeats
+    let mut compress_repeats = false;
+    let mut last_packet: Option<OscPacket> = None;
+    let mut repeats: i32 = 0;
+    //Notes and everything else that isn't streamed, kept while there is no connection
+    let mut pending: VecDeque<OscPacket> = VecDeque::new();
+    let mut param_receiver = bind_param_receiver(connection.recv_port);
+    let mut last_incoming_poll = Instant::now();
+    //A failed connect, or a stream transport that lost its peer since
+    let mut connect_error: Option<String> = None;
+    let mut reported_error: Option<String> = None;
+    let mut reported_connected = false;
+    //Where the last failed connect was going, tried again once `retry_at` has passed. The
+    //first connect goes through here too so it is retried the same way
+    let mut retry = Some(connection);
+    //What the transport is bound to, see initialize
+    let mut bound = (transport::DEFAULT_BIND_ADDRESS.to_string(), 0);
+    let mut retry_at = Instant::now();
+    let mut retry_delay = RECONNECT_DELAY_MIN;
+    //Heartbeats sent so far and when the next one is due, only while connected
+    let mut heartbeats: i32 = 0;
+    let mut heartbeat_at = Instant::now();
+    loop {
+        let error = connect_error.as_deref().or(transport.connection_error());
+        if error != reported_error.as_deref() || connected != reported_connected {
+            reported_error = error.map(str::to_string);
+            reported_connected = connected;
+            *connection_status.write() = match &reported_error {
+                Some(e) => ConnectionStatus::ConnectError(e.clone()),
+                None if connected => ConnectionStatus::Connected,
+                None => ConnectionStatus::Disconnected,
+            };
+        }
+        //While a hello_ack is due or param values can come in wake up regularly to look for them
+        let waiting = handshake_state.read().is_waiting();
+        if waiting {
+            handshake::poll(&mut transport, &addresses.base, &handshake_state);
+        }
+        if let Some(param_receiver) = &param_receiver {
+            if last_incoming_poll.elapsed() >= INCOMING_POLL_INTERVAL {
+                param_receiver.poll(
+                    &addresses.base,
+                    |name, value| forward_incoming_param(&incoming, name, value),
+                    |source, message| forward_received(&received, source, message),
+                );
+                last_incoming_poll = Instant::now();
+            }
+        }
+        //Read every time around so the interval can change without reconnecting. A failed
+        //connect skips them until the retry gets through
+        let heartbeat = params.flag_heartbeat.value() && connected;
+        if heartbeat && heartbeat_at <= Instant::now() {
+            send_packet(&mut transport, &heartbeat_packet(&addresses, heartbeats));
+            heartbeats = heartbeats.wrapping_add(1);
+            let interval = params.heartbeat_interval.value().max(1) as u64;
+            heartbeat_at = Instant::now() + Duration::from_millis(interval);
+        }
+        let poll_interval = if waiting {
+            Some(HANDSHAKE_POLL_INTERVAL)
+        } else if param_receiver.is_some() {
+            Some(INCOMING_POLL_INTERVAL)
+        } else {
+            None
+        };
+        //A pending retry or heartbeat wakes the worker up too, an Exit still gets through
+        //straight away
+        let wake_at = [retry.as_ref().map(|_| retry_at), heartbeat.then_some(heartbeat_at)]
+            .into_iter()
+            .flatten()
+            .min();
+        let poll_interval = match wake_at {
+            Some(wake_at) => {
+                let until_wake = wake_at.saturating_duration_since(Instant::now());
+                Some(poll_interval.map_or(until_wake, |interval| interval.min(until_wake)))
+            }
+            None => poll_interval,
+        };
+        let channel_message = match retry.take() {
+            Some(connection) if retry_at <= Instant::now() => {
+                nih_trace!("Retrying connection to {}:{}", connection.ip, connection.port);
+                OscChannelMessageType::ConnectionChange(connection)
+            }
+            pending_retry => {
+                retry = pending_retry;
+                match poll_interval {
+                    Some(poll_interval) => match recv.recv_timeout(poll_interval) {
+                        Ok(channel_message) => channel_message,
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    },
+                    None => match recv.recv() {
+                        Ok(channel_message) => channel_message,
+                        Err(_) => break,
+                    },
+                }
+            }
+        };
+        //Whatever came in, the plugin wants the worker gone
+        let channel_message = if stop.load(Ordering::Acquire) {
+            OscChannelMessageType::Exit
+        } else {
+            channel_message
+        };
+        //Dropped before anything is built, the destination doesn't want these
+        if channel_message
+            .category()
+            .is_some_and(|category| !categories.allows(category))
+        {
+            continue;
+        }
+        //Streamed audio and the transport are stale by the time a connection is back and
+        //connecting sends the param ranges anyway, everything else is held
+        let held_offline = channel_message.category() != Some(MessageCategory::Audio)
+            && !matches!(
+                channel_message,
+                OscChannelMessageType::Transport(_) | OscChannelMessageType::SendMeta
+            );
+        let trigger_offset_ms = match &channel_message {
+            OscChannelMessageType::NoteOn(message) if message.trigger => {
+                Some(message.time_offset_ms)
+            }
+            _ => None,
+        };
+        let packet = match channel_message {
+            OscChannelMessageType::Exit => {
+                //Tells a receiver watching the heartbeat that the plugin stopped on purpose
+                if params.flag_heartbeat.value() && connected {
+                    send_packet(
+                        &mut transport,
+                        &OscPacket::Message(OscMessage {
+                            addr: addresses.bye.clone(),
+                            args: Vec::new(),
+                        }),
+                    );
+                }
+                transport.close();
+                break;
+            }
+            OscChannelMessageType::ConnectionChange(message) => {
+                //A new destination replaces the one that was being retried
+                retry = None;
+                let ip_port = format!("{}:{}", message.ip, message.port);
+                let handshake = message.handshake;
+                nih_trace!("Connection Change: {} over {}", ip_port, message.transport);
+                let recv_port = param_receiver.as_ref().map_or(0, ParamReceiver::port);
+                if message.recv_port != recv_port {
+                    //The old socket has to let go of its port before it can be bound again
+                    drop(param_receiver.take());
+                    param_receiver = bind_param_receiver(message.recv_port);
+                }
+                //A socket can't be bound again, a new bind needs a new transport
+                let bind = (message.bind_address.clone(), message.bind_port);
+                let connect_result = if message.transport == transport.mode() && bind == bound {
+                    transport
+                        .set_ttl(message.ttl)
+                        .and_then(|_| transport.set_baud_rate(message.baud_rate))
+                        .and_then(|_| transport.connect(&message.ip, message.port))
+                } else {
+                    transport.close();
+                    transport::create_transport(
+                        message.transport,
+                        &message.ip,
+                        message.port,
+                        message.ttl,
+                        message.baud_rate,
+                        &message.bind_address,
+                        message.bind_port,
+                    )
+                    .map(|new_transport| {
+                        transport.replace(new_transport);
+                        bound = bind;
+                    })
+                };
+                match connect_result {
+                    Ok(_) => {
+                        connected = true;
+                        connect_error = None;
+                        retry_delay = RECONNECT_DELAY_MIN;
+                        transport.set_extra_destinations(transport::resolve_destinations(
+                            &message.extra_destinations,
+                        ));
+                        param_values.clear();
+                        //A new receiver has nothing to repeat
+                        last_packet = None;
+                        send_packet(&mut transport, &info_packet(&addresses, &info));
+                        if categories.allows(MessageCategory::Params) {
+                            let meta = meta_packet(&params, &param_names, &addresses);
+                            send_to_receiver(&mut transport, &meta, &handshake_state);
+                        }
+                        handshake::start(
+                            &mut transport,
+                            &addresses.base,
+                            handshake,
+                            &handshake_state,
+                        );
+                        let resend: Vec<&OscParamType> = match message.resend {
+                            ReconnectResend::Nothing => Vec::new(),
+                            ReconnectResend::Changed => changed_params
+                                .iter()
+                                .filter_map(|index| last_params.get(index))
+                                .collect(),
+                            ReconnectResend::All => last_params.values().collect(),
+                        };
+                        nih_trace!("Resending {} params", resend.len());
+                        for param in resend {
+                            if let Some(packet) =
+                                param_packet(param, &param_names, &mut param_values, &addresses)
+                            {
+                                send_packet(&mut transport, &packet);
+                            }
+                        }
+                        changed_params.clear();
+                        nih_trace!("Sending {} held packets", pending.len());
+                        for packet in pending.drain(..) {
+                            send_to_receiver(&mut transport, &packet, &handshake_state);
+                        }
+                    }
+                    Err(e) => {
+                        connected = false;
+                        nih_error!("Failed to connect to {} {:?}", ip_port, e);
+                        connect_error = Some(format!("Failed to connect to {}: {}", ip_port, e));
+                        //Waits longer after every failure so a receiver that is gone for good
+                        //doesn't fill the log
+                        nih_trace!("Retrying in {:?}", retry_delay);
+                        retry = Some(message);
+                        retry_at = Instant::now() + retry_delay;
+                        retry_delay = (retry_delay * 2).min(RECONNECT_DELAY_MAX);
+                    }
+                }
+                continue;
+            }
+            OscChannelMessageType::Info(message) => {
+                info = message;
+                continue;
+            }
+            OscChannelMessageType::CategoriesChange(message) => {
+                nih_trace!("Categories Change: {:?}", message);
+                categories = message;
+                continue;
             }
+            //Only ever sent by the worker
+            OscChannelMessageType::IncomingParam(_) | OscChannelMessageType::Received(_) => {
+                continue
+            }
+            OscChannelMessageType::RepeatsChange(message) => {
+                nih_trace!("Compress Repeats: {}", message);
+                compress_repeats = message;
+                last_packet = None;
+                continue;
+            }
+            OscChannelMessageType::AddressBaseChange(message) => {
+                addresses = OscAddresses::new(&message.address);
+                last_packet = None;
+                nih_trace!("AddressBase Change: {}", addresses.base);
+                continue;
+            }
+            OscChannelMessageType::ParamAddressesChange(message) => {
+                param_names = ParamNames::new(&params, &message.addresses);
+                last_packet = None;
+                continue;
+            }
+            OscChannelMessageType::Param(message) => {
+                if !connected {
+                    //Held until the connection is back, only the latest value matters
+                    changed_params.insert(message.index);
+                    last_params.insert(message.index, message);
+                    continue;
+                }
+                let packet = param_packet(&message, &param_names, &mut param_values, &addresses);
+                last_params.insert(message.index, message);
+                match packet {
+                    Some(packet) => packet,
+                    None => continue,
+                }
+            }
+            OscChannelMessageType::ParamBatch(message) => {
+                let mut content = Vec::new();
+                for param in message.params.into_iter().flatten() {
+                    if !connected {
+                        changed_params.insert(param.index);
+                    } else if let Some(packet) =
+                        param_packet(&param, &param_names, &mut param_values, &addresses)
+                    {
+                        content.push(packet);
+                    }
+                    last_params.insert(param.index, param);
+                }
+                //Relative params have nothing to send on their first change
+                if content.is_empty() {
+                    continue;
+                }
+                OscPacket::Bundle(OscBundle {
+                    timetag: osc_time(message.time_offset_ms),
+                    content,
+                })
+            }
+            OscChannelMessageType::ParamDigest(message) => {
+                let values = message.values.iter().map(|value| message.format.osc_arg(*value));
+                let args = if message.with_names {
+                    values
+                        .enumerate()
+                        .flat_map(|(index, value)| {
+                            let name = param_names.get(index, message.slugify_name);
+                            [OscType::String(name.to_string()), value]
+                        })
+                        .collect()
+                } else {
+                    values.collect()
+                };
+                OscPacket::Message(OscMessage {
+                    addr: addresses.params.clone(),
+                    args,
+                })
+            }
+            OscChannelMessageType::ParamModulation(message) => OscPacket::Message(OscMessage {
+                addr: addresses.param(param_names.get(message.index, message.slugify_name), "/mod"),
+                args: vec![
+                    OscType::Int(message.voice_id),
+                    OscType::Float(message.value),
+                ],
+            }),
+            OscChannelMessageType::ParamExpression(message) => OscPacket::Message(OscMessage {
+                addr: addresses.param(
+                    param_names.get(message.index, message.slugify_name),
+                    "/expression",
+                ),
+                args: vec![
+                    OscType::Int(message.voice_id),
+                    OscType::Int(message.channel as i32),
+                    OscType::Int(message.note as i32),
+                    OscType::Float(message.value),
+                ],
+            }),
+            //Pitch and velocity on their own addresses, bundled so they arrive together
+            OscChannelMessageType::NoteOn(message) if message.split => {
+                OscPacket::Bundle(OscBundle {
+                    timetag: osc_time(message.time_offset_ms),
+                    content: vec![
+                        OscPacket::Message(OscMessage {
+                            addr: addresses.note_pitch.clone(),
+                            args: vec![OscType::Int(message.note as i32)],
+                        }),
+                        OscPacket::Message(OscMessage {
+                            addr: addresses.note_velocity.clone(),
+                            args: vec![message.velocity_arg()],
+                        }),
+                    ],
+                })
+            }
+            OscChannelMessageType::NoteOn(message) => OscPacket::Message(OscMessage {
+                addr: addresses.note_on.clone(),
+                args: vec![
+                    OscType::Int(message.channel as i32),
+                    OscType::Int(message.note as i32),
+                    message.velocity_arg(),
+                ],
+            }),
+            OscChannelMessageType::NoteOff(message) => OscPacket::Message(OscMessage {
+                addr: addresses.note_off.clone(),
+                args: vec![
+                    OscType::Int(message.channel as i32),
+                    OscType::Int(message.note as i32),
+                    message.velocity_arg(),
+                ],
+            }),
+            OscChannelMessageType::NamedCc(message) => OscPacket::Message(OscMessage {
+                addr: addresses.under_base(message.name),
+                args: vec![
+                    OscType::Int(message.channel as i32),
+                    message.format.osc_arg(message.value),
+                ],
+            }),
+            OscChannelMessageType::Cc(message) => midi_value_packet(addresses.cc.clone(), &message),
+            OscChannelMessageType::PitchBend(message) => {
+                midi_value_packet(addresses.pitchbend.clone(), &message)
+            }
+            OscChannelMessageType::Aftertouch(message) => {
+                let addr = match message.number {
+                    Some(_) => addresses.poly_aftertouch.clone(),
+                    None => addresses.aftertouch.clone(),
+                };
+                midi_value_packet(addr, &message)
+            }
+            OscChannelMessageType::Audio(message) => OscPacket::Message(OscMessage {
+                addr: addresses.audio.clone(),
+                args: message.samples[..message.channels]
+                    .iter()
+                    .map(|&sample| OscType::Float(sample))
+                    .collect(),
+            }),
+            OscChannelMessageType::AudioBlock(message) => OscPacket::Message(OscMessage {
+                addr: addresses.audio_block.clone(),
+                args: vec![
+                    OscType::Double(message.time),
+                    OscType::Int(message.channels as i32),
+                    //Big endian like every other number in OSC
+                    OscType::Blob(
+                        message.samples.iter().flat_map(|sample| sample.to_be_bytes()).collect(),
+                    ),
+                ],
+            }),
+            OscChannelMessageType::AudioMeter(message) => {
+                let levels = |addr: &String, levels: &[f32]| {
+                    OscPacket::Message(OscMessage {
+                        addr: addr.clone(),
+                        args: levels.iter().map(|&level| OscType::Float(level)).collect(),
+                    })
+                };
+                OscPacket::Bundle(OscBundle {
+                    timetag: OSC_TIME_IMMEDIATELY,
+                    content: vec![
+                        levels(&addresses.audio_peak, &message.peak[..message.channels]),
+                        levels(&addresses.audio_rms, &message.rms[..message.channels]),
+                    ],
+                })
+            }
+            OscChannelMessageType::SampleCount(message) => OscPacket::Message(OscMessage {
+                addr: addresses.samples.clone(),
+                args: vec![OscType::Long(message.samples as i64)],
+            }),
+            OscChannelMessageType::Transport(message) => {
+                match transport_packet(&addresses, &message) {
+                    Some(packet) => packet,
+                    None => continue,
+                }
+            }
+            OscChannelMessageType::AudioZcr(message) => OscPacket::Message(OscMessage {
+                addr: addresses.audio_zcr.clone(),
+                args: vec![OscType::Float(message.value)],
+            }),
+            OscChannelMessageType::Onset(message) => OscPacket::Message(OscMessage {
+                addr: addresses.under_base(message.band.address()),
+                args: vec![OscType::Float(message.strength)],
+            }),
+            OscChannelMessageType::SendMeta => meta_packet(&params, &param_names, &addresses),
+            OscChannelMessageType::AllNotesOff(message) => {
+                all_notes_off_packet(&addresses, message.int_velocity)
+            }
+            OscChannelMessageType::Custom(message) => {
+                let addr = if message.address.starts_with('/') {
+                    message.address
+                } else {
+                    addresses.under_base(&message.address)
+                };
+                OscPacket::Message(OscMessage {
+                    addr,
+                    args: message.args,
+                })
+            }
+        };
+        //Trigger-only receivers get a bang next to the full note
+        let packet = match trigger_offset_ms {
+            Some(time_offset_ms) => OscPacket::Bundle(OscBundle {
+                timetag: osc_time(time_offset_ms),
+                content: vec![
+                    packet,
+                    OscPacket::Message(OscMessage {
+                        addr: addresses.trigger.clone(),
+                        args: vec![OscType::Inf],
+                    }),
+                ],
+            }),
+            None => packet,
+        };
+        if connected && compress_repeats {
+            if last_packet.as_ref() == Some(&packet) {
+                repeats += 1;
+                let repeat = OscPacket::Message(OscMessage {
+                    addr: addresses.repeat.clone(),
+                    args: vec![OscType::Int(repeats)],
+                });
+                send_packet(&mut transport, &repeat);
+                continue;
+            }
+            last_packet = Some(packet.clone());
+            repeats = 0;
+        }
+        if connected {
+            send_to_receiver(&mut transport, &packet, &handshake_state);
+        } else if held_offline {
+            if pending.len() == PENDING_PACKETS_LIMIT {
+                pending.pop_front();
+            }
+            pending.push_back(packet);
+        }
+    }
+}
+
+/// Less jitter when the system is busy. Raising it needs permissions the host often doesn't
+/// have on Linux, the worker runs at normal priority then
+fn raise_worker_priority() {
+    match thread_priority::set_current_thread_priority(ThreadPriority::Max) {
+        Ok(_) => nih_trace!("Background thread running at high priority"),
+        Err(e) => nih_error!("Failed to raise background thread priority, keeping normal {:?}", e),
+    }
+}
+
+/// 0 turns receiving off, a port that can't be bound is logged and nothing is received
+fn bind_param_receiver(port: u16) -> Option<ParamReceiver> {
+    if port == 0 {
+        return None;
+    }
+    match ParamReceiver::bind(port) {
+        Ok(param_receiver) => {
+            nih_trace!("Receiving params on port {}", port);
+            Some(param_receiver)
+        }
+        Err(e) => {
+            nih_error!("Failed to receive params on port {} {:?}", port, e);
+            None
+        }
+    }
+}
+
+/// Dropped when the editor hasn't picked up the values before it, it's closed then
+fn forward_incoming_param(incoming: &Sender<OscChannelMessageType>, name: &str, value: f32) {
+    let message = OscChannelMessageType::IncomingParam(OscIncomingParamType {
+        name: name.to_string(),
+        value,
+    });
+    if incoming.try_send(message).is_err() {
+        nih_trace!("Dropped incoming value of param {}", name);
+    }
+}
+
+/// Dropped like incoming params while the editor is closed
+fn forward_received(
+    received: &Sender<OscChannelMessageType>,
+    source: SocketAddr,
+    message: &OscMessage,
+) {
+    let args: Vec<String> = message.args.iter().map(transport::format_arg).collect();
+    let message = OscChannelMessageType::Received(OscReceivedType {
+        source,
+        address: message.addr.clone(),
+        args: args.join(" "),
+    });
+    let _ = received.try_send(message);
+}
+
+/// Bundles only go out to receivers that can unpack them
+fn send_to_receiver(
+    transport: &mut dyn OscTransport,
+    packet: &OscPacket,
+    handshake_state: &RwLock<HandshakeState>,
+) {
+    if handshake_state.read().supports_bundles() {
+        send_packet(transport, packet);
+    } else {
+        send_unbundled(transport, packet);
+    }
+}
+
+/// Every fixed address under the base, built once per base so a message only copies its address
+/// instead of formatting it
+struct OscAddresses {
+    /// Formatted, with a leading slash and no trailing one
+    base: String,
+    info: String,
+    params: String,
+    note_on: String,
+    note_off: String,
+    note_pitch: String,
+    note_velocity: String,
+    trigger: String,
+    all_notes_off: String,
+    cc: String,
+    pitchbend: String,
+    aftertouch: String,
+    poly_aftertouch: String,
+    audio: String,
+    audio_block: String,
+    audio_zcr: String,
+    audio_peak: String,
+    audio_rms: String,
+    samples: String,
+    transport_bpm: String,
+    transport_playing: String,
+    transport_position: String,
+    repeat: String,
+    heartbeat: String,
+    bye: String,
+}
+
+impl OscAddresses {
+    fn new(address_base: &str) -> Self {
+        let base = format_osc_address_base(address_base);
+        let address = |name: &str| format!("{}/{}", base, name);
+        Self {
+            info: address("info"),
+            params: address("params"),
+            note_on: address("note_on"),
+            note_off: address("note_off"),
+            note_pitch: address("note/pitch"),
+            note_velocity: address("note/velocity"),
+            trigger: address("trigger"),
+            all_notes_off: address("all_notes_off"),
+            cc: address("cc"),
+            pitchbend: address("pitchbend"),
+            aftertouch: address("aftertouch"),
+            poly_aftertouch: address("aftertouch/poly"),
+            audio: address("audio"),
+            audio_block: address("audio/block"),
+            audio_zcr: address("audio/zcr"),
+            audio_peak: address("audio/peak"),
+            audio_rms: address("audio/rms"),
+            samples: address("samples"),
+            transport_bpm: address("transport/bpm"),
+            transport_playing: address("transport/playing"),
+            transport_position: address("transport/position"),
+            repeat: address("repeat"),
+            heartbeat: address("heartbeat"),
+            bye: address("bye"),
+            base,
+        }
+    }
+
+    /// `<base>/<name>` for addresses that aren't known up front, a single allocation of the
+    /// right size
+    fn under_base(&self, name: &str) -> String {
+        let mut address = String::with_capacity(self.base.len() + 1 + name.len());
+        address.push_str(&self.base);
+        address.push('/');
+        address.push_str(name);
+        address
+    }
+
+    /// `<base>/param/<name><suffix>`
+    fn param(&self, name: &str, suffix: &str) -> String {
+        let mut address =
+            String::with_capacity(self.base.len() + "/param/".len() + name.len() + suffix.len());
+        address.push_str(&self.base);
+        address.push_str("/param/");
+        address.push_str(name);
+        address.push_str(suffix);
+        address
+    }
+}
+
+/// `None` when the send mode has nothing to send for this change
+fn param_packet(
+    message: &OscParamType,
+    param_names: &ParamNames,
+    param_values: &mut HashMap<usize, f32>,
+    addresses: &OscAddresses,
+) -> Option<OscPacket> {
+    let previous = param_values.insert(message.index, message.value);
+    let name = param_names.get(message.index, message.slugify_name);
+    match (message.mode, previous) {
+        (ParamSendMode::Comparator, previous) => {
+            let crossing = previous
+                .and_then(|previous| message.comparator.crossing(previous, message.value))?;
+            Some(OscPacket::Message(OscMessage {
+                addr: addresses.param(name, ""),
+                args: vec![OscType::Bool(crossing)],
+            }))
+        }
+        //Already latched to 0 or 1 by the audio thread
+        (ParamSendMode::Toggle, _) => Some(OscPacket::Message(OscMessage {
+            addr: addresses.param(name, ""),
+            args: vec![message.format.osc_arg(message.value)],
+        })),
+        (ParamSendMode::Absolute, _) | (ParamSendMode::Hybrid, None) => {
+            let mut args = vec![message.format.osc_arg(message.value)];
+            //The first send has nothing before it, the value stands in for itself
+            if message.with_previous {
+                args.push(message.format.osc_arg(previous.unwrap_or(message.value)));
+            }
+            Some(OscPacket::Message(OscMessage {
+                addr: addresses.param(name, ""),
+                args,
+            }))
+        }
+        (_, Some(previous)) => Some(OscPacket::Message(OscMessage {
+            addr: addresses.param(name, "/relative"),
+            args: vec![message.format.osc_delta_arg(message.value - previous)],
+        })),
+        //Nothing to measure from yet
+        (ParamSendMode::Relative, None) => None,
+    }
+}
+
+/// Each change on its own address, bundled when more than one changed in the same buffer
+fn transport_packet(addresses: &OscAddresses, message: &OscTransportType) -> Option<OscPacket> {
+    let mut content = Vec::new();
+    let mut add = |addr: &String, arg: OscType| {
+        content.push(OscPacket::Message(OscMessage {
+            addr: addr.clone(),
+            args: vec![arg],
+        }))
+    };
+    if let Some(playing) = message.playing {
+        add(&addresses.transport_playing, OscType::Bool(playing));
+    }
+    if let Some(tempo) = message.tempo {
+        add(&addresses.transport_bpm, OscType::Float(tempo as f32));
+    }
+    if let Some(position) = message.position {
+        add(&addresses.transport_position, OscType::Double(position));
+    }
+    match content.len() {
+        0 => None,
+        1 => content.pop(),
+        _ => Some(OscPacket::Bundle(OscBundle {
+            timetag: OSC_TIME_IMMEDIATELY,
+            content,
+        })),
+    }
+}
+
+/// A note off for all 128 notes of all 16 channels, then `/all_notes_off` for receivers that
+/// handle it in one go. The notes are in bundles that fit in a datagram so splitting keeps
+/// them whole
+fn all_notes_off_packet(addresses: &OscAddresses, int_velocity: bool) -> OscPacket {
+    let velocity = if int_velocity {
+        OscType::Int(0)
+    } else {
+        OscType::Float(0.0)
+    };
+    let note_off = |channel: i32, note: i32| {
+        OscPacket::Message(OscMessage {
+            addr: addresses.note_off.clone(),
+            args: vec![OscType::Int(channel), OscType::Int(note), velocity.clone()],
+        })
+    };
+    //Every note off is the same size. A bundle has a 16 byte header and every message in it
+    //is prefixed with its 4 byte size
+    let note_off_size = rosc::encoder::encode(&note_off(0, 0))
+        .map_or(transport::MAX_UDP_PACKET_SIZE, |buf| buf.len());
+    let notes_per_bundle = ((transport::MAX_UDP_PACKET_SIZE - 16) / (note_off_size + 4)).max(1);
+    let mut content: Vec<OscPacket> = (0..16)
+        .flat_map(|channel| (0..128).step_by(notes_per_bundle).map(move |first| (channel, first)))
+        .map(|(channel, first)| {
+            let last = (first + notes_per_bundle as i32).min(128);
+            OscPacket::Bundle(OscBundle {
+                timetag: OSC_TIME_IMMEDIATELY,
+                content: (first..last).map(|note| note_off(channel, note)).collect(),
+            })
+        })
+        .collect();
+    content.push(OscPacket::Message(OscMessage {
+        addr: addresses.all_notes_off.clone(),
+        args: Vec::new(),
+    }));
+    OscPacket::Bundle(OscBundle {
+        timetag: OSC_TIME_IMMEDIATELY,
+        content,
+    })
+}
+
+/// `<channel> [<number>] <value>`, the number is left out when the message has none
+fn midi_value_packet(addr: String, message: &OscMidiValueType) -> OscPacket {
+    let mut args = vec![OscType::Int(message.channel as i32)];
+    if let Some(number) = message.number {
+        args.push(OscType::Int(number as i32));
+    }
+    args.push(message.format.osc_arg(message.value));
+    OscPacket::Message(OscMessage { addr, args })
+}
+
+/// Range, default and current value of every param for receivers that map params on their
+/// own, in the format and with the `Bipolar` setting the param is sent with. Sent whenever we
+/// connect
+fn meta_packet(
+    params: &OsClapParams,
+    param_names: &ParamNames,
+    addresses: &OscAddresses,
+) -> OscPacket {
+    let format = params.param_value_format.value();
+    let slugify_name = params.flag_slugify_names.value();
+    let normalized = params.flag_normalized_params.value();
+    let configs = *params.param_configs.read();
+    let content = (0..NUM_PARAMS)
+        .map(|index| {
+            let param = &params[index];
+            let name = param_names.get(index, slugify_name);
+            let output = |value| {
+                let value = osc_param_value(param, value, normalized);
+                format.osc_arg(format.output_value(value, configs[index].bipolar))
+            };
+            OscPacket::Message(OscMessage {
+                addr: addresses.under_base(&format!("meta/param/{}", name)),
+                args: vec![
+                    output(param.preview_plain(0.0)),
+                    output(param.preview_plain(1.0)),
+                    output(param.default_plain_value()),
+                    output(param.value()),
+                ],
+            })
+        })
+        .collect();
+    OscPacket::Bundle(OscBundle {
+        timetag: OSC_TIME_IMMEDIATELY,
+        content,
+    })
+}
+
+/// Tells the receiver who is sending and at what rates, sent whenever we connect
+fn info_packet(addresses: &OscAddresses, info: &OscInfoType) -> OscPacket {
+    OscPacket::Message(OscMessage {
+        addr: addresses.info.clone(),
+        args: vec![
+            OscType::String(OsClap::VERSION.to_string()),
+            OscType::Float(info.sample_rate),
+            OscType::Int(info.osc_sample_rate),
+        ],
+    })
+}
+
+/// Lets a receiver tell an idle plugin from one that is gone, the count shows missed ones
+fn heartbeat_packet(addresses: &OscAddresses, count: i32) -> OscPacket {
+    OscPacket::Message(OscMessage {
+        addr: addresses.heartbeat.clone(),
+        args: vec![OscType::Int(count)],
+    })
+}
+
+/// For receivers that can't unpack bundles, every message in them goes out on its own
+fn send_unbundled(transport: &mut dyn OscTransport, packet: &OscPacket) {
+    match packet {
+        OscPacket::Bundle(bundle) => {
+            for part in &bundle.content {
+                send_unbundled(transport, part);
+            }
+        }
+        OscPacket::Message(_) => send_packet(transport, packet),
+    }
+}
+
+/// Encode and send a packet. Datagrams are atomic so anything over the transport limit is
+/// never going to arrive whole, bundles get split in half until the parts fit.
+fn send_packet(transport: &mut dyn OscTransport, packet: &OscPacket) {
+    let buf = match rosc::encoder::encode(packet) {
+        Ok(buf) => buf,
+        Err(e) => {
+            nih_error!("Failed to encode osc message {:?}", e);
+            transport.dropped();
+            return;
+        }
+    };
+    if let Some(max_packet_size) = transport.max_packet_size() {
+        if buf.len() > max_packet_size {
+            match packet {
+                OscPacket::Bundle(bundle) if bundle.content.len() > 1 => {
+                    nih_trace!("Splitting {} byte bundle", buf.len());
+                    let (first, second) = bundle.content.split_at(bundle.content.len() / 2);
+                    for content in [first, second] {
+                        let part = OscPacket::Bundle(OscBundle {
+                            timetag: bundle.timetag,
+                            content: content.to_vec(),
+                        });
+                        send_packet(transport, &part);
+                    }
+                }
+                _ => {
+                    nih_error!(
+                        "Dropped {} byte osc packet, the limit is {} bytes",
+                        buf.len(),
+                        max_packet_size
+                    );
+                    transport.dropped();
+                }
+            }
+            return;
+        }
+    }
+    match transport.send(&buf[..]) {
+        Ok(len) if len != buf.len() => {
+            nih_error!("Dropped osc packet, only {} of {} bytes sent", len, buf.len())
         }
-        ProcessStatus::Normal
+        Ok(_) => nih_trace!("Sent {:?} packet", packet),
+        Err(e) => nih_error!("Failed to send osc message {:?}", e),
     }
 }
 
-impl OsClap {
-    fn process_params(&self) -> Result<()> {
-        self.send_dirty_param(&self.p1_dirty, &self.params.param1)?;
-        self.send_dirty_param(&self.p2_dirty, &self.params.param2)?;
-        self.send_dirty_param(&self.p3_dirty, &self.params.param3)?;
-        self.send_dirty_param(&self.p4_dirty, &self.params.param4)?;
-        self.send_dirty_param(&self.p5_dirty, &self.params.param5)?;
-        self.send_dirty_param(&self.p6_dirty, &self.params.param6)?;
-        self.send_dirty_param(&self.p7_dirty, &self.params.param7)?;
-        self.send_dirty_param(&self.p8_dirty, &self.params.param8)?;
-        Ok(())
+/// The longer of the param's own minimum interval and the interval of the max rate
+fn param_min_interval(min_interval_ms: u32, max_rate: i32) -> Duration {
+    let min_interval = Duration::from_millis(min_interval_ms as u64);
+    match max_rate {
+        0 => min_interval,
+        max_rate => min_interval.max(Duration::from_secs(1) / max_rate as u32),
     }
+}
 
-    fn send_dirty_param(&self, param_dirty: &Arc<AtomicBool>, param: &FloatParam) -> Result<()> {
-        if param_dirty
-            .compare_exchange(true, false, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok()
-        {
-            nih_trace!("Param Dirty: {} {}", param.name(), param.value());
-            self.sender
-                .send(OscChannelMessageType::Param(OscParamType {
-                    name: param.name().to_string(), //TODO: allocation
-                    value: param.value(),
-                }))?;
-        }
-        Ok(())
+/// The name a param is sent under, a blank address falls back to the param's own name
+fn param_osc_name<'a>(address: &'a str, param: &'a FloatParam) -> &'a str {
+    match address.trim() {
+        "" => param.name(),
+        address => address,
     }
+}
 
-    fn process_event(&self, event: &NoteEvent<()>) -> Result<()> {
-        match *event {
-            NoteEvent::NoteOn {
-                timing: _,
-                channel,
-                note,
-                velocity,
-                voice_id: _,
-            } => self
-                .sender
-                .send(OscChannelMessageType::NoteOn(OscNoteType {
-                    channel,
-                    note,
-                    velocity,
-                }))?,
-            NoteEvent::NoteOff {
-                timing: _,
-                channel,
-                note,
-                velocity,
-                voice_id: _,
-            } => self
-                .sender
-                .send(OscChannelMessageType::NoteOff(OscNoteType {
-                    channel,
-                    note,
-                    velocity,
-                }))?,
-            _ => {}
-        };
-        Ok(())
+/// The name a param is received under, a blank receive address falls back to the send name
+fn param_receive_osc_name<'a>(
+    receive_address: &'a str,
+    address: &'a str,
+    param: &'a FloatParam,
+) -> &'a str {
+    match receive_address.trim() {
+        "" => param_osc_name(address, param),
+        receive_address => receive_address,
     }
+}
 
-    fn process_audio_buffer(&mut self, buffer: &mut Buffer) -> Result<()> {
-        if let Some(resampler) = &mut self.resampler {
-            if let Some(resampler_buffer) = &mut self.resampler_buffer {
-                //TODO: deal with a create mono signal or send out multiple channels?
-                resampler.process_into_buffer(&buffer.as_slice(), resampler_buffer, None)?;
-                //TODO: we only use the first channel
-                for &sample in &resampler_buffer[0] {
-                    if sample == 0.0 {
-                        continue;
-                    }
-                    let send_result = self
-                        .sender
-                        .send(OscChannelMessageType::Audio(OscAudioType { value: sample }));
-                    if send_result.is_err() {
-                        nih_error!("Failed to send processed audio {:?}", send_result.unwrap_err());
-                        break;
-                    }
-                }
-            }
+/// The name every param goes into its address under, slugified or not. The audio thread only
+/// passes the index, the worker works the names out once per change of the addresses instead
+/// of for every packet
+struct ParamNames {
+    names: [String; NUM_PARAMS],
+    slugified: [String; NUM_PARAMS],
+}
+
+impl ParamNames {
+    fn new(params: &OsClapParams, param_addresses: &[String; NUM_PARAMS]) -> Self {
+        let name = |index: usize, slugify_name| {
+            let name = param_osc_name(&param_addresses[index], &params[index]);
+            param_address_name(name, slugify_name)
+        };
+        Self {
+            names: std::array::from_fn(|index| name(index, false)),
+            slugified: std::array::from_fn(|index| name(index, true)),
         }
-        Ok(())
     }
 
-    fn kill_background_thread(&mut self) {
-        let exit_result = self.sender.send(OscChannelMessageType::Exit);
-        if exit_result.is_err() {
-            nih_error!(
-                "Failed to send shutdown to background thread {:?}",
-                exit_result.unwrap_err()
-            );
+    fn get(&self, index: usize, slugify_name: bool) -> &str {
+        if slugify_name {
+            &self.slugified[index]
+        } else {
+            &self.names[index]
         }
-        self.osc_thread = None;
     }
 }
 
-// /<osc_address_base>/param/<param_name>
-// /<osc_address_base>/note_on <channel> <note> <velocity>
-// /<osc_address_base>/note_off <channel> <note> <velocity>
-// /<osc_address_base>/audio
+/// The param name as it goes into an address, a single part so any slash becomes an underscore
+fn param_address_name(name: &str, slugify_name: bool) -> String {
+    if slugify_name {
+        return slugify(name);
+    }
+    let name = sanitize_osc_address(name).replace('/', "_");
+    if name.is_empty() {
+        return "unnamed".to_string();
+    }
+    name
+}
 
-fn osc_client_worker(
-    socket: UdpSocket,
-    param_address_base: String,
-    recv: Receiver<OscChannelMessageType>,
-) -> () {
-    nih_trace!("Background thread spawned!");
-    nih_trace!("Background thread OSC Address Base: {}", param_address_base);
-    let mut address_base = format_osc_address_base(&param_address_base);
-    let mut connected = true; //We assume the socket we get is good
-    while let Some(channel_message) = recv.recv().ok() {
-        let osc_message = match channel_message {
-            OscChannelMessageType::Exit => break,
-            OscChannelMessageType::ConnectionChange(message) => {
-                let ip_port = format!("{}:{}", message.ip, message.port);
-                nih_trace!("Connection Change: {}", ip_port);
-                let socket_result = socket.connect(&ip_port);
-                match socket_result {
-                    Ok(_) => connected = true,
-                    Err(e) => {
-                        connected = false;
-                        nih_error!("Failed to connect to {} {:?}", ip_port, e);
-                    }
-                }
-                continue;
-            }
-            OscChannelMessageType::AddressBaseChange(message) => {
-                address_base = format_osc_address_base(&message.address);
-                nih_trace!("AddressBase Change: {}", address_base);
-                continue;
-            }
-            OscChannelMessageType::Param(message) => OscMessage {
-                addr: format!("{}/param/{}", address_base, message.name),
-                args: vec![OscType::Float(message.value)],
-            },
-            OscChannelMessageType::NoteOn(message) => OscMessage {
-                addr: format!("{}/note_on", address_base),
-                args: vec![
-                    OscType::Int(message.channel as i32),
-                    OscType::Int(message.note as i32),
-                    OscType::Float(message.velocity),
-                ],
-            },
-            OscChannelMessageType::NoteOff(message) => OscMessage {
-                addr: format!("{}/note_off", address_base),
-                args: vec![
-                    OscType::Int(message.channel as i32),
-                    OscType::Int(message.note as i32),
-                    OscType::Float(message.velocity),
-                ],
-            },
-            OscChannelMessageType::Audio(message) => OscMessage {
-                addr: format!("{}/audio", address_base),
-                args: vec![OscType::Float(message.value)],
-            },
-        };
-        if connected {
-            let packet = OscPacket::Message(osc_message);
-            let buf = match rosc::encoder::encode(&packet) {
-                Ok(buf) => buf,
-                Err(e) => {
-                    nih_error!("Failed to encode osc message {:?}", e);
-                    continue;
-                }
-            };
-            let len = match socket.send(&buf[..]) {
-                Ok(buf) => buf,
-                Err(e) => {
-                    nih_error!("Failed to send osc message {:?}", e);
-                    continue;
-                }
-            };
-            if len != buf.len() {
-                nih_trace!("UDP packet not fully sent");
-            }
-            nih_trace!("Sent {:?} packet", packet);
+/// Lowercase with every run of anything that isn't a letter or digit turned into a single
+/// underscore, so "Cutoff Freq." becomes "cutoff_freq"
+fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('_') {
+            slug.push('_');
         }
     }
+    while slug.ends_with('_') {
+        slug.pop();
+    }
+    if slug.is_empty() {
+        return "unnamed".to_string();
+    }
+    slug
 }
 
 fn format_osc_address_base(raw_base: &str) -> String {
-    if raw_base.is_empty() {
+    let base = sanitize_osc_address(raw_base);
+    if base.is_empty() {
         return "".to_string();
     } else {
-        return format!("/{}", raw_base); //Prefix with slash
+        return format!("/{}", base); //Prefix with slash
+    }
+}
+
+/// Whitespace becomes an underscore and the characters OSC reserves for address patterns
+/// are dropped, so "my synth/" becomes "my_synth". Runs of slashes are collapsed and the
+/// leading and trailing ones trimmed
+fn sanitize_osc_address(raw: &str) -> String {
+    let mut address = String::with_capacity(raw.len());
+    for c in raw.trim().chars() {
+        match c {
+            '#' | '*' | ',' | '?' | '[' | ']' | '{' | '}' => {}
+            '/' if address.is_empty() || address.ends_with('/') => {}
+            c if c.is_whitespace() => address.push('_'),
+            c if c.is_control() => {}
+            c => address.push(c),
+        }
+    }
+    while address.ends_with('/') {
+        address.pop();
     }
+    address
 }
 
 impl ClapPlugin for OsClap {
@@ -637,7 +4285,10 @@ impl ClapPlugin for OsClap {
 
     const CLAP_SUPPORT_URL: Option<&'static str> = None;
 
-    const CLAP_POLY_MODULATION_CONFIG: Option<PolyModulationConfig> = None;
+    const CLAP_POLY_MODULATION_CONFIG: Option<PolyModulationConfig> = Some(PolyModulationConfig {
+        max_voice_capacity: 64,
+        supports_overlapping_voices: true,
+    });
 }
 
 // impl Vst3Plugin for OsClap {
@@ -647,3 +4298,738 @@ impl ClapPlugin for OsClap {
 
 nih_export_clap!(OsClap);
 //nih_export_vst3!(OsClap);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_no_alloc::{assert_no_alloc, AllocDisabler};
+    use std::net::UdpSocket;
+
+    //Only checks inside `assert_no_alloc`, everything else allocates as usual
+    #[global_allocator]
+    static ALLOCATOR: AllocDisabler = AllocDisabler;
+
+    const RECV_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// A socket of the test on loopback for the worker to send to, on a port the OS picks
+    fn loopback_receiver() -> UdpSocket {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver.set_read_timeout(Some(RECV_TIMEOUT)).unwrap();
+        receiver
+    }
+
+    /// A worker sending to a socket of the test on loopback, both on ports the OS picks
+    fn spawn_worker(
+        address_base: &str,
+    ) -> (Sender<OscChannelMessageType>, JoinHandle<()>, UdpSocket) {
+        let receiver = loopback_receiver();
+        let transport =
+            transport::open_transport(OscTransportMode::Udp, 1, "127.0.0.1", 0).unwrap();
+        let transport = ObservedTransport::new(
+            transport,
+            Arc::new(RwLock::new(None)),
+            Arc::new(SendStats::new()),
+        );
+        let connection = udp_connection(receiver.local_addr().unwrap().port());
+        let status = WorkerStatus {
+            handshake_state: Arc::new(RwLock::new(HandshakeState::Disabled)),
+            incoming: OscChannel::incoming().sender,
+            received: OscChannel::received().sender,
+            connection_status: Arc::new(RwLock::new(ConnectionStatus::Disconnected)),
+            stop: Arc::new(AtomicBool::new(false)),
+        };
+        let param_dirty = Arc::new(std::array::from_fn(|_| AtomicBool::new(false)));
+        let params = Arc::new(OsClapParams::new(&param_dirty));
+        let info = OscInfoType {
+            sample_rate: 48000.0,
+            osc_sample_rate: 100,
+        };
+        let channel = OscChannel::default();
+        let address_base = address_base.to_string();
+        let worker = thread::spawn(move || {
+            osc_client_worker(
+                transport,
+                params,
+                address_base,
+                info,
+                connection,
+                status,
+                CategoryMask::default(),
+                channel.receiver,
+            )
+        });
+        (channel.sender, worker, receiver)
+    }
+
+    fn udp_connection(port: u16) -> OscConnectionType {
+        OscConnectionType {
+            transport: OscTransportMode::Udp,
+            ip: "127.0.0.1".to_string(),
+            port,
+            handshake: false,
+            ttl: 1,
+            baud_rate: transport::DEFAULT_BAUD_RATE,
+            resend: ReconnectResend::Changed,
+            recv_port: 0,
+            extra_destinations: Vec::new(),
+            bind_address: "127.0.0.1".to_string(),
+            bind_port: 0,
+        }
+    }
+
+    fn param(index: usize, value: f32) -> OscChannelMessageType {
+        OscChannelMessageType::Param(OscParamType {
+            index,
+            value,
+            mode: ParamSendMode::Absolute,
+            comparator: ParamComparator::default(),
+            format: ParamValueFormat::Float,
+            slugify_name: false,
+            with_previous: false,
+        })
+    }
+
+    /// The next message with an address under `prefix`, bundles are unpacked and everything
+    /// else the worker sends on its own, like the info on connect, is skipped
+    fn next_message(receiver: &UdpSocket, prefix: &str) -> OscMessage {
+        let mut buf = vec![0u8; 65_536];
+        loop {
+            let len = receiver.recv(&mut buf).expect("no packet from the worker");
+            let (_, packet) = rosc::decoder::decode_udp(&buf[..len]).unwrap();
+            let mut packets = vec![packet];
+            while let Some(packet) = packets.pop() {
+                match packet {
+                    OscPacket::Message(message) if message.addr.starts_with(prefix) => {
+                        return message
+                    }
+                    OscPacket::Message(_) => {}
+                    OscPacket::Bundle(bundle) => packets.extend(bundle.content.into_iter().rev()),
+                }
+            }
+        }
+    }
+
+    struct TestInitContext;
+
+    impl InitContext<OsClap> for TestInitContext {
+        fn plugin_api(&self) -> PluginApi {
+            PluginApi::Clap
+        }
+
+        fn execute(&self, _task: OsClapTask) {}
+
+        fn set_latency_samples(&self, _samples: u32) {}
+
+        fn set_current_voice_capacity(&self, _capacity: u32) {}
+    }
+
+    /// A stopped transport without tempo or position
+    struct TestProcessContext {
+        transport: Transport,
+    }
+
+    impl Default for TestProcessContext {
+        fn default() -> Self {
+            //nih_plug keeps the position fields of `Transport` to itself and has no public
+            //constructor. Every field is a bool, a number or an `Option` of those, which all
+            //have a valid all zero value. The public ones are set below to not depend on it
+            let mut transport: Transport = unsafe { std::mem::zeroed() };
+            transport.playing = false;
+            transport.recording = false;
+            transport.preroll_active = None;
+            transport.sample_rate = 48000.0;
+            transport.tempo = None;
+            transport.time_sig_numerator = None;
+            transport.time_sig_denominator = None;
+            Self { transport }
+        }
+    }
+
+    impl ProcessContext<OsClap> for TestProcessContext {
+        fn plugin_api(&self) -> PluginApi {
+            PluginApi::Clap
+        }
+
+        fn execute_background(&self, _task: OsClapTask) {}
+
+        fn execute_gui(&self, _task: OsClapTask) {}
+
+        fn transport(&self) -> &Transport {
+            &self.transport
+        }
+
+        fn next_event(&mut self) -> Option<NoteEvent<()>> {
+            None
+        }
+
+        fn send_event(&mut self, _event: NoteEvent<()>) {}
+
+        fn set_latency_samples(&self, _samples: u32) {}
+
+        fn set_current_voice_capacity(&self, _capacity: u32) {}
+    }
+
+    fn initialize(plugin: &mut OsClap) -> bool {
+        let buffer_config = BufferConfig {
+            sample_rate: 48000.0,
+            min_buffer_size: None,
+            max_buffer_size: 512,
+            process_mode: ProcessMode::Realtime,
+        };
+        plugin.initialize(&OsClap::AUDIO_IO_LAYOUTS[0], &buffer_config, &mut TestInitContext)
+    }
+
+    fn wait_for_exit(worker: &JoinHandle<()>) -> bool {
+        let deadline = Instant::now() + RECV_TIMEOUT;
+        while !worker.is_finished() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        true
+    }
+
+    #[test]
+    fn worker_sends_param_as_one_float() {
+        let (sender, worker, receiver) = spawn_worker("base");
+        sender.send(param(0, 0.5)).unwrap();
+        let message = next_message(&receiver, "/base/param/");
+        assert_eq!(message.addr, "/base/param/param1");
+        assert_eq!(message.args, vec![OscType::Float(0.5)]);
+        sender.send(OscChannelMessageType::Exit).unwrap();
+        assert!(wait_for_exit(&worker));
+    }
+
+    #[test]
+    fn worker_uses_new_address_base() {
+        let (sender, worker, receiver) = spawn_worker("base");
+        sender.send(param(0, 0.25)).unwrap();
+        assert_eq!(next_message(&receiver, "/base/param/").addr, "/base/param/param1");
+        sender
+            .send(OscChannelMessageType::AddressBaseChange(OscAddressBaseType {
+                address: "other".to_string(),
+            }))
+            .unwrap();
+        sender.send(param(0, 0.75)).unwrap();
+        let message = next_message(&receiver, "/other/param/");
+        assert_eq!(message.addr, "/other/param/param1");
+        assert_eq!(message.args, vec![OscType::Float(0.75)]);
+        sender.send(OscChannelMessageType::Exit).unwrap();
+        assert!(wait_for_exit(&worker));
+    }
+
+    #[test]
+    fn worker_renames_params_in_queue_order() {
+        let (sender, worker, receiver) = spawn_worker("base");
+        sender.send(param(0, 0.25)).unwrap();
+        let mut addresses: [String; NUM_PARAMS] = Default::default();
+        addresses[0] = "cutoff".to_string();
+        sender
+            .send(OscChannelMessageType::ParamAddressesChange(OscParamAddressesType {
+                addresses,
+            }))
+            .unwrap();
+        sender.send(param(0, 0.75)).unwrap();
+        let message = next_message(&receiver, "/base/param/");
+        assert_eq!(message.addr, "/base/param/param1");
+        assert_eq!(message.args, vec![OscType::Float(0.25)]);
+        let message = next_message(&receiver, "/base/param/");
+        assert_eq!(message.addr, "/base/param/cutoff");
+        assert_eq!(message.args, vec![OscType::Float(0.75)]);
+        sender.send(OscChannelMessageType::Exit).unwrap();
+        assert!(wait_for_exit(&worker));
+    }
+
+    /// The params sent again after the connection was lost and is back, param1 was sent before
+    /// it was lost and param2 changed while it was
+    fn resent_params(resend: ReconnectResend) -> Vec<String> {
+        let (sender, worker, receiver) = spawn_worker("base");
+        let port = receiver.local_addr().unwrap().port();
+        sender.send(param(0, 0.25)).unwrap();
+        assert_eq!(next_message(&receiver, "/base/param/").addr, "/base/param/param1");
+        //Port 0 can't be connected to
+        let lost = OscConnectionType { resend, ..udp_connection(0) };
+        sender.send(OscChannelMessageType::ConnectionChange(lost)).unwrap();
+        sender.send(param(1, 0.75)).unwrap();
+        let back = OscConnectionType { resend, ..udp_connection(port) };
+        sender.send(OscChannelMessageType::ConnectionChange(back)).unwrap();
+        sender
+            .send(OscChannelMessageType::Custom(OscCustomType {
+                address: "marker".to_string(),
+                args: Vec::new(),
+            }))
+            .unwrap();
+        let mut resent = Vec::new();
+        loop {
+            let message = next_message(&receiver, "/base/");
+            if message.addr == "/base/marker" {
+                break;
+            }
+            if message.addr.starts_with("/base/param/") {
+                resent.push(message.addr);
+            }
+        }
+        sender.send(OscChannelMessageType::Exit).unwrap();
+        assert!(wait_for_exit(&worker));
+        resent.sort();
+        resent
+    }
+
+    #[test]
+    fn reconnect_resends_changed_params() {
+        assert_eq!(resent_params(ReconnectResend::Changed), vec!["/base/param/param2"]);
+    }
+
+    #[test]
+    fn reconnect_resends_all_params() {
+        assert_eq!(
+            resent_params(ReconnectResend::All),
+            vec!["/base/param/param1", "/base/param/param2"]
+        );
+    }
+
+    #[test]
+    fn reconnect_resends_nothing() {
+        assert!(resent_params(ReconnectResend::Nothing).is_empty());
+    }
+
+    #[test]
+    fn process_params_does_not_allocate() {
+        let mut plugin = OsClap::default();
+        let mut context = TestProcessContext::default();
+        for dirty in plugin.param_dirty.iter() {
+            dirty.store(true, Ordering::Release);
+        }
+        assert_no_alloc(|| plugin.process_params(&mut context)).unwrap();
+        let sent = plugin
+            .receiver
+            .try_iter()
+            .filter(|message| matches!(message, OscChannelMessageType::Param(_)))
+            .count();
+        assert_eq!(sent, NUM_PARAMS);
+    }
+
+    #[test]
+    fn stopped_transport_is_sent_once() {
+        let mut plugin = OsClap::default();
+        let context = TestProcessContext::default();
+        plugin.process_transport(context.transport()).unwrap();
+        plugin.process_transport(context.transport()).unwrap();
+        let sent: Vec<_> = plugin
+            .receiver
+            .try_iter()
+            .filter_map(|message| match message {
+                OscChannelMessageType::Transport(transport) => Some(transport),
+                _ => None,
+            })
+            .collect();
+        let stopped = OscTransportType {
+            playing: Some(false),
+            ..OscTransportType::default()
+        };
+        assert!(sent == vec![stopped]);
+    }
+
+    #[test]
+    fn worker_stops_on_exit() {
+        let (sender, worker, _receiver) = spawn_worker("base");
+        sender.send(OscChannelMessageType::Exit).unwrap();
+        assert!(wait_for_exit(&worker));
+    }
+
+    #[test]
+    fn initialize_twice_keeps_one_worker() {
+        let mut plugin = OsClap::default();
+        assert!(initialize(&mut plugin));
+        let first = plugin.osc_thread.as_ref().unwrap().thread().id();
+        assert!(initialize(&mut plugin));
+        assert_eq!(plugin.osc_thread.as_ref().unwrap().thread().id(), first);
+        let started = Instant::now();
+        plugin.deactivate();
+        assert!(started.elapsed() < WORKER_STOP_TIMEOUT);
+        assert!(plugin.osc_thread.is_none());
+        assert!(initialize(&mut plugin));
+        assert_ne!(plugin.osc_thread.as_ref().unwrap().thread().id(), first);
+        plugin.deactivate();
+        assert!(plugin.osc_thread.is_none());
+    }
+
+    #[test]
+    fn resampler_refuses_zero_length_or_mismatched_buffers() {
+        let mut resampler = FftFixedOut::<f32>::new(48000, 1000, AUDIO_BLOCK_FRAMES, 2, 2).unwrap();
+        let frames = resampler.input_frames_next();
+        let mut output = resampler.output_buffer_allocate(true);
+
+        let mut empty = vec![Vec::new(), Vec::new()];
+        assert!(resample_chunk(&mut resampler, &mut empty, &mut output).is_err());
+
+        let mut short = vec![vec![0.5; frames], vec![0.5; frames - 1]];
+        assert!(resample_chunk(&mut resampler, &mut short, &mut output).is_err());
+        assert_eq!(short[0].len(), frames);
+
+        let mut mono = vec![vec![0.5; frames]];
+        assert!(resample_chunk(&mut resampler, &mut mono, &mut output).is_err());
+
+        let mut queue = vec![vec![0.5; frames]; 2];
+        let mut no_output: Vec<Vec<f32>> = vec![Vec::new(), Vec::new()];
+        assert!(resample_chunk(&mut resampler, &mut queue, &mut no_output).is_err());
+        let mut one_output = vec![vec![0.0; AUDIO_BLOCK_FRAMES]];
+        assert!(resample_chunk(&mut resampler, &mut queue, &mut one_output).is_err());
+
+        let (input_frames, output_frames) =
+            resample_chunk(&mut resampler, &mut queue, &mut output).unwrap();
+        assert_eq!(input_frames, frames);
+        assert_eq!(output_frames, AUDIO_BLOCK_FRAMES);
+        assert!(queue.iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn resampler_swaps_in_without_allocating() {
+        let mut plugin = OsClap::default();
+        plugin.input_sample_rate = 48000.0;
+        plugin.max_buffer_size = 512;
+        let settings = plugin.resampler_settings();
+        plugin.resampler = build_resampler(settings);
+        let executor = plugin.task_executor();
+
+        //Built for a rate that has changed again since, left for the next build to replace
+        let stale = ResamplerSettings {
+            osc_sample_rate: settings.osc_sample_rate + 1,
+            ..settings
+        };
+        executor(OsClapTask::BuildResampler(stale));
+        assert_no_alloc(|| plugin.swap_resampler().unwrap());
+        assert!(plugin.resampler_swap.lock().built.is_some());
+        assert!(plugin.receiver.try_recv().is_err());
+
+        executor(OsClapTask::BuildResampler(settings));
+        assert_no_alloc(|| plugin.swap_resampler().unwrap());
+        assert!(plugin.resampler.is_some());
+        let swap = plugin.resampler_swap.lock();
+        assert!(swap.built.is_none());
+        assert!(swap.retired.is_some());
+        drop(swap);
+        assert!(matches!(plugin.receiver.try_recv(), Ok(OscChannelMessageType::Info(_))));
+    }
+
+    #[test]
+    fn stuck_worker_is_left_behind() {
+        let mut plugin = OsClap::default();
+        let (done_sender, done_receiver) = crossbeam_channel::bounded::<()>(0);
+        plugin.osc_thread_done = done_receiver;
+        plugin.osc_thread = Some(thread::spawn(move || {
+            let _done = done_sender;
+            thread::sleep(WORKER_STOP_TIMEOUT * 3);
+        }));
+        let started = Instant::now();
+        plugin.kill_background_thread();
+        assert!(started.elapsed() >= WORKER_STOP_TIMEOUT);
+        assert!(started.elapsed() < WORKER_STOP_TIMEOUT * 2);
+        assert!(plugin.osc_thread.is_none());
+    }
+
+    #[test]
+    fn editor_messages_are_kept_for_the_next_worker() {
+        let mut plugin = OsClap::default();
+        let receiver = loopback_receiver();
+        let mut connection = plugin.params.connection();
+        connection.transport = OscTransportMode::Udp;
+        connection.ip = "127.0.0.1".to_string();
+        connection.port = receiver.local_addr().unwrap().port();
+        plugin.params.store_connection(&connection);
+        //A worker that stopped before taking anything off the channel
+        plugin.osc_thread = Some(thread::spawn(|| {}));
+        plugin
+            .sender
+            .send(OscChannelMessageType::Custom(OscCustomType {
+                address: "kept".to_string(),
+                args: vec![OscType::Int(1)],
+            }))
+            .unwrap();
+        plugin
+            .sender
+            .send(OscChannelMessageType::Audio(OscAudioFrameType {
+                samples: [0.5; MAX_AUDIO_CHANNELS],
+                channels: 1,
+            }))
+            .unwrap();
+        plugin.sender.send(OscChannelMessageType::Exit).unwrap();
+        plugin.kill_background_thread();
+        assert!(plugin.osc_thread.is_none());
+
+        assert!(initialize(&mut plugin));
+        let address_base = format_osc_address_base(&plugin.params.osc_address_base.read());
+        let message = next_message(&receiver, &format!("{}/kept", address_base));
+        assert_eq!(message.args, vec![OscType::Int(1)]);
+        //The stale Exit was dropped, the new worker is still running
+        assert!(!plugin.osc_thread.as_ref().unwrap().is_finished());
+        plugin.deactivate();
+    }
+
+    #[test]
+    fn skip_silence_leaves_out_silent_frames() {
+        let count_audio = |skip_silence: bool| {
+            let channel = OscChannel::default();
+            let sender = RealtimeSender {
+                sender: Arc::new(channel.sender),
+                dropped: Arc::new(AtomicU64::new(0)),
+                enabled: true,
+            };
+            //Silent frames need every channel at zero, frame 6 is only silent on the first
+            let mut resampled = vec![
+                (0..8).map(|i| (i % 2) as f32).collect::<Vec<f32>>(),
+                (0..8).map(|i| (i % 2) as f32).collect::<Vec<f32>>(),
+            ];
+            resampled[1][6] = 0.5;
+            send_audio_frames(&sender, &resampled, 8, skip_silence);
+            channel
+                .receiver
+                .try_iter()
+                .filter(|message| matches!(message, OscChannelMessageType::Audio(_)))
+                .count()
+        };
+        assert_eq!(count_audio(false), 8);
+        assert_eq!(count_audio(true), 5);
+    }
+
+    #[test]
+    fn audio_blocks_do_not_allocate() {
+        let (sender, receiver) = crossbeam_channel::bounded(2);
+        let sender = RealtimeSender {
+            sender: Arc::new(sender),
+            dropped: Arc::new(AtomicU64::new(0)),
+            enabled: true,
+        };
+        let pool = AudioBlockPool::default();
+        let resampled = vec![vec![0.25; AUDIO_BLOCK_FRAMES], vec![-0.25; AUDIO_BLOCK_FRAMES]];
+        //The third block doesn't fit in the channel and goes back to the pool on the spot
+        assert_no_alloc(|| {
+            for block in 0..3 {
+                send_audio_block(&sender, &pool, block as f64, &resampled).unwrap();
+            }
+        });
+        assert_eq!(sender.dropped.load(Ordering::Relaxed), 1);
+
+        let blocks: Vec<OscChannelMessageType> = receiver.try_iter().collect();
+        assert_eq!(blocks.len(), 2);
+        let OscChannelMessageType::AudioBlock(block) = &blocks[0] else {
+            panic!("expected an audio block");
+        };
+        assert_eq!(block.channels, 2);
+        assert_eq!(&block.samples[..4], &[0.25, -0.25, 0.25, -0.25]);
+        assert_eq!(block.samples.len(), AUDIO_BLOCK_FRAMES * 2);
+        drop(blocks);
+
+        //Sent blocks hand their buffer back too, without a free one the block is dropped
+        let held: Vec<Vec<f32>> = pool.receiver.try_iter().collect();
+        assert_eq!(held.len(), AUDIO_BLOCK_POOL_SIZE);
+        assert_no_alloc(|| send_audio_block(&sender, &pool, 0.0, &resampled).unwrap());
+        assert_eq!(sender.dropped.load(Ordering::Relaxed), 2);
+        drop(held);
+    }
+
+    #[test]
+    fn rapid_connection_edits_end_on_the_last_one() {
+        let mut plugin = OsClap::default();
+        let receiver = loopback_receiver();
+        let final_port = receiver.local_addr().unwrap().port();
+        let earlier: Vec<UdpSocket> = (0..3).map(|_| loopback_receiver()).collect();
+        let ports: Vec<u16> = earlier
+            .iter()
+            .map(|socket| socket.local_addr().unwrap().port())
+            .chain([final_port])
+            .collect();
+        let params = plugin.params.clone();
+        let sender = plugin.sender.clone();
+        //Like the editor committing every edit while the host initializes the plugin
+        let editor = thread::spawn(move || {
+            for port in ports {
+                let mut connection = params.connection();
+                connection.transport = OscTransportMode::Udp;
+                connection.ip = "127.0.0.1".to_string();
+                connection.port = port;
+                params.commit_connection(connection, &sender).unwrap();
+            }
+        });
+        assert!(initialize(&mut plugin));
+        editor.join().unwrap();
+        assert_eq!(plugin.params.connection().port, final_port);
+
+        plugin
+            .sender
+            .send(OscChannelMessageType::Custom(OscCustomType {
+                address: "after".to_string(),
+                args: vec![OscType::Int(1)],
+            }))
+            .unwrap();
+        let address_base = format_osc_address_base(&plugin.params.osc_address_base.read());
+        let message = next_message(&receiver, &format!("{}/after", address_base));
+        assert_eq!(message.args, vec![OscType::Int(1)]);
+        plugin.deactivate();
+    }
+
+    #[test]
+    fn param_address_name_keeps_the_name_without_slugify() {
+        assert_eq!(param_address_name("Cutoff Freq", false), "Cutoff_Freq");
+        assert_eq!(param_address_name("  Drive  ", false), "Drive");
+        assert_eq!(param_address_name("MiXeD CaSe", false), "MiXeD_CaSe");
+        assert_eq!(param_address_name("Gain (dB)", false), "Gain_(dB)");
+        assert_eq!(param_address_name("Res #2?", false), "Res_2");
+        assert_eq!(param_address_name("Mix/Wet", false), "Mix_Wet");
+        assert_eq!(param_address_name("/Mix//Wet/", false), "Mix_Wet");
+        assert_eq!(param_address_name("", false), "unnamed");
+        assert_eq!(param_address_name("*?#", false), "unnamed");
+    }
+
+    #[test]
+    fn param_address_name_slugifies() {
+        assert_eq!(param_address_name("Cutoff Freq.", true), "cutoff_freq");
+        assert_eq!(param_address_name("  Drive  ", true), "drive");
+        assert_eq!(param_address_name("MiXeD CaSe", true), "mixed_case");
+        assert_eq!(param_address_name("Gain (dB)", true), "gain_db");
+        assert_eq!(param_address_name("Res #2?", true), "res_2");
+        assert_eq!(param_address_name("Mix/Wet", true), "mix_wet");
+        assert_eq!(param_address_name("A -- B", true), "a_b");
+        assert_eq!(param_address_name("", true), "unnamed");
+        assert_eq!(param_address_name("---", true), "unnamed");
+    }
+
+    #[test]
+    fn param_receive_name_falls_back_to_the_send_name() {
+        let plugin = OsClap::default();
+        let param = &plugin.params[2];
+        assert_eq!(param_receive_osc_name("", "", param), param.name());
+        assert_eq!(param_receive_osc_name("  ", "cutoff", param), "cutoff");
+        assert_eq!(param_receive_osc_name("set_cutoff", "cutoff", param), "set_cutoff");
+        assert_eq!(param_receive_osc_name("set_cutoff", "", param), "set_cutoff");
+    }
+
+    #[test]
+    fn pitch_bend_is_centered_in_either_format() {
+        let arg =
+            |value, format: ParamValueFormat| format.osc_arg(format.output_value(value, true));
+        assert_eq!(arg(0.0, ParamValueFormat::Float), OscType::Float(-1.0));
+        assert_eq!(arg(0.5, ParamValueFormat::Float), OscType::Float(0.0));
+        assert_eq!(arg(1.0, ParamValueFormat::Float), OscType::Float(1.0));
+        assert_eq!(arg(0.0, ParamValueFormat::Midi), OscType::Int(0));
+        assert_eq!(arg(0.5, ParamValueFormat::Midi), OscType::Int(64));
+        assert_eq!(arg(1.0, ParamValueFormat::Midi), OscType::Int(127));
+    }
+
+    #[test]
+    fn midi_values_are_clamped() {
+        let midi = ParamValueFormat::Midi;
+        assert_eq!(midi.osc_arg(-1.0), OscType::Int(0));
+        assert_eq!(midi.osc_arg(0.0), OscType::Int(0));
+        assert_eq!(midi.osc_arg(0.5), OscType::Int(64));
+        assert_eq!(midi.osc_arg(1.0), OscType::Int(127));
+        assert_eq!(midi.osc_arg(2.0), OscType::Int(127));
+        assert_eq!(midi.osc_delta_arg(-1.0), OscType::Int(-127));
+        assert_eq!(midi.osc_delta_arg(-2.0), OscType::Int(-127));
+        assert_eq!(midi.osc_delta_arg(0.5), OscType::Int(64));
+        assert_eq!(ParamValueFormat::Float.osc_arg(-1.0), OscType::Float(-1.0));
+    }
+
+    #[test]
+    fn all_notes_off_bundles_fit_in_a_datagram() {
+        let addresses = OscAddresses::new("/a_rather_long_address_base");
+        let OscPacket::Bundle(bundle) = all_notes_off_packet(&addresses, false) else {
+            panic!("All notes off is a bundle");
+        };
+        let mut note_offs = 0;
+        for part in &bundle.content {
+            assert!(rosc::encoder::encode(part).unwrap().len() <= transport::MAX_UDP_PACKET_SIZE);
+            if let OscPacket::Bundle(notes) = part {
+                note_offs += notes.content.len();
+            }
+        }
+        assert_eq!(note_offs, 16 * 128);
+    }
+
+    #[test]
+    fn oversize_packets_count_as_failed_sends() {
+        let receiver = loopback_receiver();
+        let mut transport =
+            transport::open_transport(OscTransportMode::Udp, 1, "127.0.0.1", 0).unwrap();
+        transport
+            .connect("127.0.0.1", receiver.local_addr().unwrap().port())
+            .unwrap();
+        let stats = Arc::new(SendStats::new());
+        let mut transport =
+            ObservedTransport::new(transport, Arc::new(RwLock::new(None)), stats.clone());
+        let packet = OscPacket::Message(OscMessage {
+            addr: "/oversize".to_string(),
+            args: vec![OscType::String("x".repeat(transport::MAX_UDP_PACKET_SIZE))],
+        });
+        send_packet(&mut transport, &packet);
+        assert_eq!(stats.totals(), (0, 0, 1));
+    }
+
+    #[test]
+    fn address_base_gets_exactly_one_leading_slash() {
+        assert_eq!(format_osc_address_base(""), "");
+        assert_eq!(format_osc_address_base("/"), "");
+        assert_eq!(format_osc_address_base("osclap"), "/osclap");
+        assert_eq!(format_osc_address_base("/osclap"), "/osclap");
+        assert_eq!(format_osc_address_base("osclap/"), "/osclap");
+        assert_eq!(format_osc_address_base("a//b"), "/a/b");
+    }
+
+    #[test]
+    fn destination_accepts_host_and_port() {
+        assert_eq!(transport::check_destination("127.0.0.1", 9000), Ok(()));
+        assert_eq!(transport::check_destination(" localhost ", 9000), Ok(()));
+        assert_eq!(transport::check_destination("my-synth.local.", 9000), Ok(()));
+        assert_eq!(transport::check_extra_destination("10.0.0.2:57120"), Ok(()));
+        assert_eq!(transport::check_extra_destination("studio-pc:8000"), Ok(()));
+        assert!(transport::check_destination("bad host", 9000).is_err());
+        assert!(transport::check_destination("-synth", 9000).is_err());
+        assert!(transport::check_destination("a..b", 9000).is_err());
+    }
+
+    #[test]
+    fn destination_refuses_ipv6() {
+        assert!(transport::check_destination("::1", 9000).is_err());
+        assert!(transport::check_destination("fe80::1", 9000).is_err());
+        assert!(transport::check_extra_destination("[::1]:9000").is_err());
+        assert!(transport::validate_destination("::1", 9000).is_err());
+    }
+
+    #[test]
+    fn destination_needs_address_and_port() {
+        assert!(transport::check_destination("", 9000).is_err());
+        assert!(transport::check_destination("   ", 9000).is_err());
+        assert!(transport::check_destination("127.0.0.1", 0).is_err());
+        assert!(transport::check_extra_destination("127.0.0.1").is_err());
+        assert!(transport::check_extra_destination(":9000").is_err());
+        assert!(transport::check_extra_destination("127.0.0.1:").is_err());
+        assert!(transport::check_extra_destination("127.0.0.1:port").is_err());
+        assert!(transport::check_extra_destination("127.0.0.1:70000").is_err());
+        assert!(transport::check_extra_destination("127.0.0.1:0").is_err());
+        assert_eq!(transport::parse_port(" 9000 "), Ok(9000));
+        assert!(transport::parse_port("").is_err());
+        assert!(transport::parse_port("0").is_err());
+        assert!(transport::parse_port("65536").is_err());
+        assert!(transport::parse_port("-1").is_err());
+        assert!(transport::parse_port("nine").is_err());
+    }
+
+    #[test]
+    fn destination_kind_spots_broadcast_and_multicast() {
+        let kind = |ip: &str| transport::destination_kind(&ip.parse().unwrap());
+        assert_eq!(kind("255.255.255.255"), "broadcast");
+        assert_eq!(kind("224.0.0.1"), "multicast");
+        assert_eq!(kind("239.255.0.1"), "multicast");
+        assert_eq!(kind("ff02::1"), "multicast");
+        assert_eq!(kind("127.0.0.1"), "unicast");
+        //A host on a bigger subnet, only the netmask tells it apart from a subnet broadcast
+        assert_eq!(kind("10.0.1.255"), "unicast");
+    }
+
+    #[test]
+    fn literal_destination_resolves_without_lookup() {
+        let destination = transport::validate_destination("127.0.0.1", 9000).unwrap();
+        assert_eq!(destination, "127.0.0.1:9000".parse().unwrap());
+    }
+}
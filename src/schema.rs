@@ -0,0 +1,305 @@
+use nih_plug::prelude::*;
+use serde::Serialize;
+
+use crate::analysis::OnsetBand;
+use crate::{
+    format_osc_address_base, param_address_name, param_osc_name, AudioMode, MessageCategory,
+    OsClap, OsClapParams, ParamExpression, ParamSendMode, ParamValueFormat, NAMED_CC_NAMES,
+};
+
+/// Everything OSCLAP can send with the current settings, written out for receiver codegen
+#[derive(Serialize)]
+pub struct AddressSchema {
+    version: &'static str,
+    address_base: String,
+    addresses: Vec<AddressEntry>,
+}
+
+#[derive(Serialize)]
+struct AddressEntry {
+    address: String,
+    /// None for messages that are always sent
+    category: Option<&'static str>,
+    args: Vec<ArgSchema>,
+}
+
+#[derive(Serialize)]
+struct ArgSchema {
+    name: &'static str,
+    /// OSC type tag, `T` stands for both bools
+    #[serde(rename = "type")]
+    type_tag: &'static str,
+    min: Option<f32>,
+    max: Option<f32>,
+}
+
+impl ArgSchema {
+    fn new(name: &'static str, type_tag: &'static str) -> Self {
+        Self {
+            name,
+            type_tag,
+            min: None,
+            max: None,
+        }
+    }
+
+    fn range(mut self, min: f32, max: f32) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// A 0-1 value as `ParamValueFormat` puts it on the wire, MIDI ints are 0-127 either way
+    fn value(name: &'static str, format: ParamValueFormat, bipolar: bool) -> Self {
+        let min = if bipolar { -1.0 } else { 0.0 };
+        match format {
+            ParamValueFormat::Float => Self::new(name, "f").range(min, 1.0),
+            ParamValueFormat::Midi => Self::new(name, "i").range(0.0, 127.0),
+        }
+    }
+}
+
+struct SchemaBuilder {
+    address_base: String,
+    addresses: Vec<AddressEntry>,
+}
+
+impl SchemaBuilder {
+    fn add(&mut self, address: &str, category: Option<MessageCategory>, args: Vec<ArgSchema>) {
+        self.addresses.push(AddressEntry {
+            address: format!("{}{}", self.address_base, address),
+            category: category.map(MessageCategory::name),
+            args,
+        });
+    }
+}
+
+/// Built from the persisted settings, so it matches what the worker is sending
+pub fn build(params: &OsClapParams) -> AddressSchema {
+    let address_base = format_osc_address_base(&params.osc_address_base.read());
+    let mut schema = SchemaBuilder {
+        address_base: address_base.clone(),
+        addresses: Vec::new(),
+    };
+    let categories = *params.osc_categories.read();
+    let format = params.param_value_format.value();
+
+    schema.add(
+        "/info",
+        None,
+        vec![
+            ArgSchema::new("version", "s"),
+            ArgSchema::new("sample_rate", "f"),
+            ArgSchema::new("osc_sample_rate", "i"),
+        ],
+    );
+    if params.flag_hello_handshake.value() {
+        schema.add("/hello", None, vec![ArgSchema::new("version", "s")]);
+    }
+    if params.flag_compress_repeats.value() {
+        schema.add("/repeat", None, vec![ArgSchema::new("count", "i")]);
+    }
+    if params.flag_heartbeat.value() {
+        schema.add("/heartbeat", None, vec![ArgSchema::new("count", "i")]);
+        schema.add("/bye", None, Vec::new());
+    }
+
+    if categories.params {
+        add_params(&mut schema, params, format);
+    }
+
+    if categories.notes && params.flag_send_midi.value() {
+        let category = Some(MessageCategory::Notes);
+        let midi_ints = params.flag_midi_int_values.value();
+        let velocity = || {
+            if midi_ints {
+                ArgSchema::new("velocity", "i").range(0.0, 127.0)
+            } else {
+                ArgSchema::new("velocity", "f").range(0.0, 1.0)
+            }
+        };
+        let note_args = || {
+            vec![
+                ArgSchema::new("channel", "i").range(0.0, 15.0),
+                ArgSchema::new("note", "i").range(0.0, 127.0),
+                velocity(),
+            ]
+        };
+        if params.flag_split_notes.value() {
+            let pitch = ArgSchema::new("note", "i").range(0.0, 127.0);
+            schema.add("/note/pitch", category, vec![pitch]);
+            schema.add("/note/velocity", category, vec![velocity()]);
+        } else {
+            schema.add("/note_on", category, note_args());
+        }
+        if params.flag_note_trigger.value() {
+            schema.add("/trigger", category, vec![ArgSchema::new("impulse", "I")]);
+        }
+        schema.add("/note_off", category, note_args());
+        schema.add("/all_notes_off", category, Vec::new());
+        let midi_format = if midi_ints { ParamValueFormat::Midi } else { format };
+        for (named_cc, name) in params.named_ccs.read().iter().zip(NAMED_CC_NAMES) {
+            if named_cc.enabled {
+                let args = vec![
+                    ArgSchema::new("channel", "i").range(0.0, 15.0),
+                    ArgSchema::value("value", midi_format, false),
+                ];
+                schema.add(&format!("/{}", name), category, args);
+            }
+        }
+        let channel = || ArgSchema::new("channel", "i").range(0.0, 15.0);
+        let cc_args = vec![
+            channel(),
+            ArgSchema::new("cc", "i").range(0.0, 127.0),
+            ArgSchema::value("value", midi_format, false),
+        ];
+        schema.add("/cc", category, cc_args);
+        let pitchbend_args = vec![channel(), ArgSchema::value("value", midi_format, true)];
+        schema.add("/pitchbend", category, pitchbend_args);
+        let aftertouch_args = vec![channel(), ArgSchema::value("pressure", midi_format, false)];
+        schema.add("/aftertouch", category, aftertouch_args);
+        let poly_aftertouch_args = vec![
+            channel(),
+            ArgSchema::new("note", "i").range(0.0, 127.0),
+            ArgSchema::value("pressure", midi_format, false),
+        ];
+        schema.add("/aftertouch/poly", category, poly_aftertouch_args);
+    }
+
+    if categories.audio && params.flag_send_audio.value() {
+        let category = Some(MessageCategory::Audio);
+        if params.audio_mode.value() == AudioMode::Meter {
+            //Per channel a mono input only has the left one
+            let levels = |names: [&'static str; 2]| match params.flag_meter_per_channel.value() {
+                true => Vec::from(names.map(|name| ArgSchema::new(name, "f").range(0.0, 1.0))),
+                false => vec![ArgSchema::new("level", "f").range(0.0, 1.0)],
+            };
+            schema.add("/audio/peak", category, levels(["left_peak", "right_peak"]));
+            schema.add("/audio/rms", category, levels(["left_rms", "right_rms"]));
+        } else if params.flag_audio_blob.value() {
+            let args = vec![
+                ArgSchema::new("time", "d"),
+                ArgSchema::new("channels", "i").range(1.0, 2.0),
+                ArgSchema::new("interleaved_samples", "b"),
+            ];
+            schema.add("/audio/block", category, args);
+        } else {
+            //A mono input only has the left one
+            let args = vec![
+                ArgSchema::new("left", "f").range(-1.0, 1.0),
+                ArgSchema::new("right", "f").range(-1.0, 1.0),
+            ];
+            schema.add("/audio", category, args);
+        }
+        if params.flag_send_zcr.value() {
+            let rate = ArgSchema::new("crossings_per_second", "f");
+            schema.add("/audio/zcr", category, vec![rate]);
+        }
+    }
+
+    if categories.audio && params.flag_send_samples.value() {
+        let count = ArgSchema::new("sample_count", "h");
+        schema.add("/samples", Some(MessageCategory::Audio), vec![count]);
+    }
+
+    if params.flag_send_transport.value() {
+        schema.add("/transport/playing", None, vec![ArgSchema::new("playing", "T")]);
+        schema.add("/transport/bpm", None, vec![ArgSchema::new("bpm", "f")]);
+        schema.add("/transport/position", None, vec![ArgSchema::new("beats", "d")]);
+    }
+
+    if categories.onsets && params.flag_send_onsets.value() {
+        for band in OnsetBand::ALL {
+            let strength = ArgSchema::new("strength", "f");
+            let address = format!("/{}", band.address());
+            schema.add(&address, Some(MessageCategory::Onsets), vec![strength]);
+        }
+    }
+
+    AddressSchema {
+        version: OsClap::VERSION,
+        address_base,
+        addresses: schema.addresses,
+    }
+}
+
+fn add_params(schema: &mut SchemaBuilder, params: &OsClapParams, format: ParamValueFormat) {
+    let category = Some(MessageCategory::Params);
+    let slugify_names = params.flag_slugify_names.value();
+    let param_addresses = params.param_addresses.read();
+    //Sent on connect whatever the params are sent as
+    for (index, config) in params.param_configs.read().iter().enumerate() {
+        let osc_name = param_osc_name(&param_addresses[index], &params[index]);
+        let name = param_address_name(osc_name, slugify_names);
+        let args = ["min", "max", "default", "value"]
+            .map(|name| ArgSchema::value(name, format, config.bipolar));
+        schema.add(&format!("/meta/param/{}", name), category, Vec::from(args));
+    }
+    if params.flag_param_digest.value() {
+        let with_names = params.flag_digest_names.value();
+        let mut args = Vec::new();
+        for config in params.param_configs.read().iter() {
+            if with_names {
+                args.push(ArgSchema::new("name", "s"));
+            }
+            args.push(ArgSchema::value("value", format, config.bipolar));
+        }
+        schema.add("/params", category, args);
+        return;
+    }
+    for (index, config) in params.param_configs.read().iter().enumerate() {
+        let osc_name = param_osc_name(&param_addresses[index], &params[index]);
+        let name = param_address_name(osc_name, slugify_names);
+        let address = format!("/param/{}", name);
+        schema.add(
+            &format!("{}/mod", address),
+            category,
+            vec![
+                ArgSchema::new("voice_id", "i"),
+                ArgSchema::new("value", "f").range(0.0, 1.0),
+            ],
+        );
+        if !config.route.osc {
+            continue;
+        }
+        if config.expression != ParamExpression::Off {
+            schema.add(
+                &format!("{}/expression", address),
+                category,
+                vec![
+                    ArgSchema::new("voice_id", "i"),
+                    ArgSchema::new("channel", "i").range(0.0, 15.0),
+                    ArgSchema::new("note", "i").range(0.0, 127.0),
+                    ArgSchema::new("value", "f").range(0.0, 1.0),
+                ],
+            );
+        }
+        let value = || ArgSchema::value("value", format, config.bipolar);
+        let relative = || {
+            let span = value().max.unwrap_or(1.0) - value().min.unwrap_or(0.0);
+            ArgSchema::value("delta", format, config.bipolar).range(-span, span)
+        };
+        match config.send_mode {
+            ParamSendMode::Absolute => {
+                let mut args = vec![value()];
+                if params.flag_send_previous.value() {
+                    args.push(ArgSchema::value("previous", format, config.bipolar));
+                }
+                schema.add(&address, category, args);
+            }
+            ParamSendMode::Relative => {
+                schema.add(&format!("{}/relative", address), category, vec![relative()]);
+            }
+            ParamSendMode::Hybrid => {
+                schema.add(&address, category, vec![value()]);
+                schema.add(&format!("{}/relative", address), category, vec![relative()]);
+            }
+            ParamSendMode::Comparator => {
+                schema.add(&address, category, vec![ArgSchema::new("rising", "T")]);
+            }
+            ParamSendMode::Toggle => {
+                schema.add(&address, category, vec![ArgSchema::value("state", format, false)]);
+            }
+        }
+    }
+}
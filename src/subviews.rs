@@ -2,66 +2,219 @@ use std::sync::Arc;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::widgets::*;
 
-use crate::{editor::OsClapEditorEvent, editor::OscSettings, OsClapParams};
+use crate::{
+    editor::OsClapEditorEvent, editor::OscSettings, transport, ConnectionStatus, MessageCategory,
+    OsClapParams, NAMED_CC_NAMES, NUM_PARAMS,
+};
 
 pub struct ParamView;
 
 impl ParamView {
-    pub fn new<P>(cx: &mut Context, params: P) -> Handle<Self>
+    /// One per param and the reset button, the window is sized from the row counts
+    pub const ROWS: u32 = NUM_PARAMS as u32 + 1;
+
+    pub fn new<P, S, R, A>(
+        cx: &mut Context,
+        params: P,
+        settings: S,
+        reset_armed: R,
+        active: A,
+    ) -> Handle<Self>
     where
         P: Lens<Target = Arc<OsClapParams>> + Copy,
+        S: Lens<Target = OscSettings> + Copy,
+        R: Lens<Target = bool>,
+        A: Lens<Target = [bool; NUM_PARAMS]> + Copy,
     {
-        //TODO handle param names
         Self.build(cx, |cx| {
+            for index in 0..NUM_PARAMS {
+                Self::param_row(cx, params, settings, active, index);
+            }
             HStack::new(cx, |cx| {
-                Label::new(cx, "param1").class("label");
-                ParamSlider::new(cx, params, |params| &params.param1)
-                    .class("widget");
-            })
-            .class("row");
-            HStack::new(cx, |cx| {
-                Label::new(cx, "param2").class("label");
-                ParamSlider::new(cx, params, |params| &params.param2)
-                    .class("widget");
+                Button::new(
+                    cx,
+                    |cx| cx.emit(OsClapEditorEvent::ResetParams),
+                    move |cx| {
+                        Label::new(
+                            cx,
+                            reset_armed.map(|armed| {
+                                if *armed {
+                                    "Confirm Reset"
+                                } else {
+                                    "Reset Params"
+                                }
+                            }),
+                        )
+                    },
+                )
+                .width(Pixels(120.0))
+                .class("widget");
             })
             .class("row");
-            HStack::new(cx, |cx| {
-                Label::new(cx, "param3").class("label");
-                ParamSlider::new(cx, params, |params| &params.param3)
-                    .class("widget");
-            })
-            .class("row");
-            HStack::new(cx, |cx| {
-                Label::new(cx, "param4").class("label");
-                ParamSlider::new(cx, params, |params| &params.param4)
-                    .class("widget");
-            })
-            .class("row");
-            HStack::new(cx, |cx| {
-                Label::new(cx, "param5").class("label");
-                ParamSlider::new(cx, params, |params| &params.param5)
-                    .class("widget");
-            })
-            .class("row");
-            HStack::new(cx, |cx| {
-                Label::new(cx, "param6").class("label");
-                ParamSlider::new(cx, params, |params| &params.param6)
-                    .class("widget");
-            })
-            .class("row");
-            HStack::new(cx, |cx| {
-                Label::new(cx, "param7").class("label");
-                ParamSlider::new(cx, params, |params| &params.param7)
-                    .class("widget");
+        })
+    }
+
+    fn param_row<P, S, A>(cx: &mut Context, params: P, settings: S, active: A, index: usize)
+    where
+        P: Lens<Target = Arc<OsClapParams>> + Copy,
+        S: Lens<Target = OscSettings> + Copy,
+        A: Lens<Target = [bool; NUM_PARAMS]> + Copy,
+    {
+        HStack::new(cx, move |cx| {
+            //Lights up while the param is being sent
+            Element::new(cx)
+                .width(Pixels(8.0))
+                .height(Pixels(8.0))
+                .background_color(active.map(move |active| activity_color(active[index])));
+            Label::new(cx, format!("param{}", index + 1)).class("label");
+            //Blank sends under the label's name
+            Textbox::new(cx, settings.map(move |settings| settings.param_addresses[index].clone()))
+                .on_edit(move |cx, text| {
+                    cx.emit(OsClapEditorEvent::SetParamAddress(index, text));
+                })
+                .on_submit(move |cx, _, _| {
+                    cx.emit(OsClapEditorEvent::ParamAddressChange(index));
+                })
+                .width(Pixels(100.0));
+            //Blank receives under the name it is sent under
+            Textbox::new(
+                cx,
+                settings.map(move |settings| settings.param_receive_addresses[index].clone()),
+            )
+            .on_edit(move |cx, text| {
+                cx.emit(OsClapEditorEvent::SetParamReceiveAddress(index, text));
             })
-            .class("row");
-            HStack::new(cx, |cx| {
-                Label::new(cx, "param8").class("label");
-                ParamSlider::new(cx, params, |params| &params.param8)
-                    .class("widget");
+            .on_submit(move |cx, _, _| {
+                cx.emit(OsClapEditorEvent::ParamReceiveAddressChange(index));
             })
-            .class("row");
+            .width(Pixels(100.0));
+            ParamSlider::new(cx, params, move |params| &params[index])
+                .class("widget");
+            Button::new(
+                cx,
+                move |cx| cx.emit(OsClapEditorEvent::CopyParamAddress(index)),
+                |cx| Label::new(cx, "Copy"),
+            )
+            .width(Pixels(50.0));
+            Button::new(
+                cx,
+                move |cx| cx.emit(OsClapEditorEvent::CycleParamSendMode(index)),
+                move |cx| Label::new(cx, settings.map(move |settings| settings.param_configs[index].send_mode.to_string())),
+            )
+            .width(Pixels(50.0));
+            Button::new(
+                cx,
+                move |cx| cx.emit(OsClapEditorEvent::ToggleParamBipolar(index)),
+                move |cx| Label::new(cx, settings.map(move |settings| bipolar_label(settings.param_configs[index].bipolar))),
+            )
+            .width(Pixels(50.0));
+            Button::new(
+                cx,
+                move |cx| cx.emit(OsClapEditorEvent::ToggleParamOsc(index)),
+                move |cx| Label::new(cx, settings.map(move |settings| route_label(settings.param_configs[index].route.osc, "OSC"))),
+            )
+            .width(Pixels(40.0));
+            Button::new(
+                cx,
+                move |cx| cx.emit(OsClapEditorEvent::ToggleParamMidi(index)),
+                move |cx| Label::new(cx, settings.map(move |settings| route_label(settings.param_configs[index].route.midi, "CC"))),
+            )
+            .width(Pixels(40.0));
+            Textbox::new(cx, settings.map(move |settings| settings.param_configs[index].route.cc))
+                .on_edit(move |cx, text| {
+                    match text.parse::<u8>() {
+                        Ok(cc) if cc < 128 => {
+                            cx.emit(OsClapEditorEvent::SetParamCc(index, cc));
+                            cx.toggle_class("invalid", false);
+                        }
+                        _ => cx.toggle_class("invalid", true),
+                    }
+                })
+                .width(Pixels(40.0));
+            Button::new(
+                cx,
+                move |cx| cx.emit(OsClapEditorEvent::CycleComparatorEdge(index)),
+                move |cx| {
+                    Label::new(
+                        cx,
+                        settings.map(move |settings| {
+                            settings.param_configs[index].comparator.edge.to_string()
+                        }),
+                    )
+                },
+            )
+            .width(Pixels(50.0));
+            let threshold =
+                settings.map(move |settings| settings.param_configs[index].comparator.threshold);
+            Textbox::new(cx, threshold)
+                .on_edit(move |cx, text| {
+                    if let Ok(threshold) = text.parse::<f32>() {
+                        cx.emit(OsClapEditorEvent::SetComparatorThreshold(index, threshold));
+                        cx.toggle_class("invalid", false);
+                    } else {
+                        cx.toggle_class("invalid", true);
+                    }
+                })
+                .width(Pixels(50.0));
+            let interval =
+                settings.map(move |settings| settings.param_configs[index].min_interval_ms);
+            Textbox::new(cx, interval)
+                .on_edit(move |cx, text| {
+                    if let Ok(interval) = text.parse::<u32>() {
+                        cx.emit(OsClapEditorEvent::SetParamInterval(index, interval));
+                        cx.toggle_class("invalid", false);
+                    } else {
+                        cx.toggle_class("invalid", true);
+                    }
+                })
+                .width(Pixels(50.0));
+            Button::new(
+                cx,
+                move |cx| cx.emit(OsClapEditorEvent::CycleParamExpression(index)),
+                move |cx| {
+                    Label::new(
+                        cx,
+                        settings.map(move |settings| {
+                            settings.param_configs[index].expression.to_string()
+                        }),
+                    )
+                },
+            )
+            .width(Pixels(60.0));
         })
+        .class("row");
+    }
+}
+
+fn bipolar_label(bipolar: bool) -> &'static str {
+    if bipolar {
+        "-1..1"
+    } else {
+        "0..1"
+    }
+}
+
+fn activity_color(active: bool) -> Color {
+    if active {
+        Color::rgb(80, 200, 120)
+    } else {
+        Color::rgb(60, 60, 60)
+    }
+}
+
+fn connection_color(status: &ConnectionStatus) -> Color {
+    match status {
+        ConnectionStatus::Disconnected => Color::rgb(60, 60, 60),
+        ConnectionStatus::Connected => Color::rgb(80, 200, 120),
+        ConnectionStatus::ConnectError(_) => Color::rgb(220, 70, 60),
+    }
+}
+
+fn route_label(enabled: bool, name: &'static str) -> &'static str {
+    if enabled {
+        name
+    } else {
+        "Off"
     }
 }
 
@@ -75,11 +228,31 @@ impl View for ParamView {
 pub struct SettingsView;
 
 impl SettingsView {
-    pub fn new<S,P,L>(cx: &mut Context, settings: S, params: P, log: L) -> Handle<Self>
+    /// Rows above the log, without the ones of extra destinations
+    pub const ROWS: u32 = 50;
+    pub const LOG_HEIGHT: f32 = 180.0;
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new<S,P,L,B,R,C,T,X>(
+        cx: &mut Context,
+        settings: S,
+        params: P,
+        log: L,
+        bandwidth: B,
+        receiver: R,
+        connection: C,
+        process_time: T,
+        send_rate: X,
+    ) -> Handle<Self>
     where
         S: Lens<Target = OscSettings> + Copy,
         P: Lens<Target = Arc<OsClapParams>> + Copy,
         L: Lens<Target = Vec<String>>,
+        B: Lens<Target = String>,
+        R: Lens<Target = String>,
+        C: Lens<Target = ConnectionStatus>,
+        T: Lens<Target = String>,
+        X: Lens<Target = String>,
     {
         Self.build(cx, |cx| {
             HStack::new(cx, |cx| {
@@ -92,11 +265,74 @@ impl SettingsView {
                     .on_submit(|cx,  _, _| {
                         cx.emit(OsClapEditorEvent::ConnectionChange);
                     })
+                    .toggle_class("invalid", settings.map(|settings| settings.destination_error.is_some()))
                     .width(Pixels(135.0)); // 200 = 135 + 60 + 5
-                Textbox::new(cx, settings.map(|settings| settings.osc_server_port))
+                //Fires on enter and when the field loses focus, half typed ports never connect
+                Textbox::new(cx, settings.map(|settings| settings.osc_server_port_text.clone()))
+                    .on_edit(move |cx, text| {
+                        cx.emit(OsClapEditorEvent::SetOscServerPort(text));
+                    })
+                    .on_submit(|cx,  _, _| {
+                        cx.emit(OsClapEditorEvent::CommitOscServerPort);
+                    })
+                    .toggle_class("invalid", settings.map(|settings| settings.port_error.is_some()))
+                    .width(Pixels(60.0));
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "").class("label");
+                Label::new(
+                    cx,
+                    settings.map(|settings| {
+                        settings
+                            .port_error
+                            .clone()
+                            .or_else(|| settings.destination_error.clone())
+                            .unwrap_or_default()
+                    }),
+                )
+                .width(Pixels(200.0))
+                .class("widget");
+            })
+            .class("row");
+            // .col_between(Pixels(5.0));
+            //One row per extra destination, they scroll with the rest of the settings
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Extra Destinations").class("label");
+                Button::new(
+                    cx,
+                    |cx| cx.emit(OsClapEditorEvent::AddExtraDestination),
+                    |cx| Label::new(cx, "Add"),
+                )
+                .width(Pixels(50.0));
+            })
+            .class("row");
+            Binding::new(
+                cx,
+                settings.map(|settings| settings.extra_destinations.len()),
+                move |cx, count| {
+                    for index in 0..count.get(cx) {
+                        Self::extra_destination_row(cx, settings, index);
+                    }
+                },
+            );
+            HStack::new(cx, |cx| {
+                Label::new(cx, "OSC Bind Address").class("label");
+                Textbox::new(cx, settings.map(|settings| settings.osc_bind_address.clone()))
                     .on_edit(move |cx, text| {
+                        let valid = transport::validate_bind_address(&text).is_ok();
+                        cx.emit(OsClapEditorEvent::SetOscBindAddress(text));
+                        cx.toggle_class("invalid", !valid);
+                    })
+                    .on_submit(|cx,  _, _| {
+                        cx.emit(OsClapEditorEvent::ConnectionChange);
+                    })
+                    .width(Pixels(135.0));
+                Textbox::new(cx, settings.map(|settings| settings.osc_bind_port))
+                    .on_edit(move |cx, text| {
+                        //0 lets the OS pick the port
                         if let Ok(val) = text.parse::<u16>() {
-                            cx.emit(OsClapEditorEvent::SetOscServerPort(val));
+                            cx.emit(OsClapEditorEvent::SetOscBindPort(val));
                             cx.toggle_class("invalid", false);
                         } else {
                             cx.toggle_class("invalid", true);
@@ -108,18 +344,262 @@ impl SettingsView {
                     .width(Pixels(60.0));
             })
             .class("row");
-            // .col_between(Pixels(5.0));
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Serial Baud Rate").class("label");
+                Textbox::new(cx, settings.map(|settings| settings.osc_baud_rate))
+                    .on_edit(move |cx, text| {
+                        if let Some(val) = text.parse::<u32>().ok().filter(|&val| val != 0) {
+                            cx.emit(OsClapEditorEvent::SetOscBaudRate(val));
+                            cx.toggle_class("invalid", false);
+                        } else {
+                            cx.toggle_class("invalid", true);
+                        }
+                    })
+                    .on_submit(|cx,  _, _| {
+                        cx.emit(OsClapEditorEvent::ConnectionChange);
+                    })
+                    .width(Pixels(100.0));
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "OSC Receive Port").class("label");
+                Textbox::new(cx, settings.map(|settings| settings.osc_recv_port))
+                    .on_edit(move |cx, text| {
+                        //0 turns receiving off
+                        if let Ok(val) = text.parse::<u16>() {
+                            cx.emit(OsClapEditorEvent::SetOscRecvPort(val));
+                            cx.toggle_class("invalid", false);
+                        } else {
+                            cx.toggle_class("invalid", true);
+                        }
+                    })
+                    .on_submit(|cx,  _, _| {
+                        cx.emit(OsClapEditorEvent::ConnectionChange);
+                    })
+                    .width(Pixels(60.0));
+                //Params can only be set through the GUI, values received while it is closed are
+                //dropped
+                Label::new(cx, "Applied while open").width(Pixels(135.0)); // 200 = 60 + 135 + 5
+            })
+            .class("row");
             HStack::new(cx, |cx| {
                 Label::new(cx, "OSC Address Base").class("label");
                 Textbox::new(cx, settings.map(|settings| settings.osc_address_base.clone()))
                     .on_edit(move |cx, text| {
-                        //TODO: validate
                         cx.emit(OsClapEditorEvent::SetOscAddressBase(text));
                     })
                     .on_submit(|cx,  _, _| {
                         cx.emit(OsClapEditorEvent::AddressBaseChange);
                     })
-                    .width(Pixels(200.0));
+                    .width(Pixels(145.0)); // 200 = 145 + 50 + 5
+                Button::new(
+                    cx,
+                    |cx| cx.emit(OsClapEditorEvent::CopyAddressBase),
+                    |cx| Label::new(cx, "Copy"),
+                )
+                .width(Pixels(50.0));
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "OSC Transport").class("label");
+                Button::new(
+                    cx,
+                    |cx| {
+                        cx.emit(OsClapEditorEvent::CycleOscTransport);
+                        cx.emit(OsClapEditorEvent::ConnectionChange);
+                    },
+                    |cx| Label::new(cx, settings.map(|settings| settings.osc_transport.to_string())),
+                )
+                .width(Pixels(100.0))
+                .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Receives").class("label");
+                for category in MessageCategory::ALL {
+                    Button::new(
+                        cx,
+                        move |cx| cx.emit(OsClapEditorEvent::ToggleCategory(category)),
+                        move |cx| {
+                            Label::new(
+                                cx,
+                                settings.map(move |settings| {
+                                    route_label(
+                                        settings.osc_categories.allows(category),
+                                        category.name(),
+                                    )
+                                }),
+                            )
+                        },
+                    )
+                    .width(Pixels(60.0));
+                }
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Reconnect Delay").class("label");
+                ParamSlider::new(cx, params, |params| &params.connection_debounce)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Reconnect Resend").class("label");
+                ParamSlider::new(cx, params, |params| &params.reconnect_resend)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "TTL").class("label");
+                ParamSlider::new(cx, params, |params| &params.osc_ttl)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Time Offset").class("label");
+                ParamSlider::new(cx, params, |params| &params.osc_time_offset)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Param Format").class("label");
+                ParamSlider::new(cx, params, |params| &params.param_value_format)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Send Previous").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_send_previous)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Bundle Params").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_bundle_params)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Normalized Params").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_normalized_params)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Param Max Rate").class("label");
+                ParamSlider::new(cx, params, |params| &params.param_max_rate)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Param Ranges").class("label");
+                Button::new(
+                    cx,
+                    |cx| cx.emit(OsClapEditorEvent::SendMeta),
+                    |cx| Label::new(cx, "Send Meta"),
+                )
+                .width(Pixels(100.0));
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Slugify Names").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_slugify_names)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Sample Accurate").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_sample_accurate)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Smooth Params").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_param_smoothing)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Param Digest").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_param_digest)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Digest Names").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_digest_names)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Param Clock").class("label");
+                ParamSlider::new(cx, params, |params| &params.clock_division)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Quiet On Load").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_quiet_on_load)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Hello Handshake").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_hello_handshake)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Heartbeat").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_heartbeat)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Heartbeat Interval").class("label");
+                ParamSlider::new(cx, params, |params| &params.heartbeat_interval)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Receiver").class("label");
+                Element::new(cx)
+                    .width(Pixels(8.0))
+                    .height(Pixels(8.0))
+                    .background_color(connection.map(connection_color));
+                Label::new(cx, receiver).class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Compress Repeats").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_compress_repeats)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "High Priority").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_high_priority)
+                    .width(Pixels(100.0))
+                    .class("widget");
             })
             .class("row");
             HStack::new(cx, |cx| {
@@ -129,6 +609,82 @@ impl SettingsView {
                     .class("widget");
             })
             .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Note Range").class("label");
+                ParamSlider::new(cx, params, |params| &params.note_range_low)
+                    .width(Pixels(100.0))
+                    .class("widget");
+                ParamSlider::new(cx, params, |params| &params.note_range_high)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Note Trigger").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_note_trigger)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Split Notes").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_split_notes)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Vel 0 Is Off").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_zero_velocity_off)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Stuck Notes").class("label");
+                Button::new(
+                    cx,
+                    |cx| cx.emit(OsClapEditorEvent::AllNotesOff),
+                    |cx| Label::new(cx, "All Notes Off"),
+                )
+                .width(Pixels(100.0));
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "MIDI Ints").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_midi_int_values)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Named CCs").class("label");
+                for (index, name) in NAMED_CC_NAMES.into_iter().enumerate() {
+                    Button::new(
+                        cx,
+                        move |cx| cx.emit(OsClapEditorEvent::ToggleNamedCc(index)),
+                        move |cx| {
+                            Label::new(
+                                cx,
+                                settings.map(move |settings| {
+                                    route_label(settings.named_ccs[index].enabled, name)
+                                }),
+                            )
+                        },
+                    )
+                    .width(Pixels(90.0));
+                    Textbox::new(cx, settings.map(move |settings| settings.named_ccs[index].cc))
+                        .on_edit(move |cx, text| match text.parse::<u8>() {
+                            Ok(cc) if cc < 128 => {
+                                cx.emit(OsClapEditorEvent::SetNamedCc(index, cc));
+                                cx.toggle_class("invalid", false);
+                            }
+                            _ => cx.toggle_class("invalid", true),
+                        })
+                        .width(Pixels(40.0));
+                }
+            })
+            .class("row");
             HStack::new(cx, |cx| {
                 Label::new(cx, "Send Audio").class("label");
                 ParamSlider::new(cx, params, |params| &params.flag_send_audio)
@@ -136,13 +692,138 @@ impl SettingsView {
                     .class("widget");
             })
             .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Audio Mode").class("label");
+                ParamSlider::new(cx, params, |params| &params.audio_mode)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Meter Per Channel").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_meter_per_channel)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Audio Blob").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_audio_blob)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Skip Silence").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_skip_silence)
+                    .width(Pixels(100.0))
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Custom Message").class("label");
+                Textbox::new(cx, settings.map(|settings| settings.custom_address.clone()))
+                    .on_edit(move |cx, text| {
+                        cx.emit(OsClapEditorEvent::SetCustomAddress(text));
+                    })
+                    .width(Pixels(95.0));
+                Textbox::new(cx, settings.map(|settings| settings.custom_args.clone()))
+                    .on_edit(move |cx, text| {
+                        cx.emit(OsClapEditorEvent::SetCustomArgs(text));
+                    })
+                    .on_submit(|cx, _, _| {
+                        cx.emit(OsClapEditorEvent::SendCustom);
+                    })
+                    .width(Pixels(55.0));
+                Button::new(
+                    cx,
+                    |cx| cx.emit(OsClapEditorEvent::SendCustom),
+                    |cx| Label::new(cx, "Send"),
+                )
+                .width(Pixels(40.0)); // 200 = 95 + 55 + 40 + 5 + 5
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Export Schema").class("label");
+                Textbox::new(cx, settings.map(|settings| settings.schema_path.clone()))
+                    .on_edit(move |cx, text| {
+                        cx.emit(OsClapEditorEvent::SetSchemaPath(text));
+                    })
+                    .on_submit(|cx, _, _| {
+                        cx.emit(OsClapEditorEvent::ExportSchema);
+                    })
+                    .width(Pixels(150.0));
+                Button::new(
+                    cx,
+                    |cx| cx.emit(OsClapEditorEvent::ExportSchema),
+                    |cx| Label::new(cx, "Export"),
+                )
+                .width(Pixels(45.0));
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Est. Bandwidth").class("label");
+                Label::new(cx, bandwidth).class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Sent").class("label");
+                Label::new(cx, send_rate).width(Pixels(135.0));
+                Button::new(
+                    cx,
+                    |cx| cx.emit(OsClapEditorEvent::ResetSendStats),
+                    |cx| Label::new(cx, "Reset"),
+                )
+                .width(Pixels(60.0));
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Process Timing").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_process_timing)
+                    .width(Pixels(100.0))
+                    .class("widget");
+                Label::new(cx, process_time).width(Pixels(100.0));
+            })
+            .class("row");
             VirtualList::new(cx, log, 20.0, |cx, _index, item| {
                 return Label::new(cx, item).left(Pixels(0.0)).class("label");
             })
-            .height(Pixels(180.0))
+            .height(Pixels(Self::LOG_HEIGHT))
             .class("row");
         })
     }
+
+    fn extra_destination_row<S>(cx: &mut Context, settings: S, index: usize)
+    where
+        S: Lens<Target = OscSettings> + Copy,
+    {
+        HStack::new(cx, move |cx| {
+            Label::new(cx, format!("Destination {}", index + 2)).class("label");
+            Textbox::new(
+                cx,
+                settings.map(move |settings| {
+                    settings.extra_destinations.get(index).cloned().unwrap_or_default()
+                }),
+            )
+            .on_edit(move |cx, text| {
+                cx.emit(OsClapEditorEvent::SetExtraDestination(index, text));
+            })
+            .on_submit(|cx, _, _| {
+                cx.emit(OsClapEditorEvent::ConnectionChange);
+            })
+            .width(Pixels(145.0)); // 200 = 145 + 50 + 5
+            Button::new(
+                cx,
+                move |cx| {
+                    cx.emit(OsClapEditorEvent::RemoveExtraDestination(index));
+                    cx.emit(OsClapEditorEvent::ConnectionChange);
+                },
+                |cx| Label::new(cx, "Remove"),
+            )
+            .width(Pixels(50.0));
+        })
+        .class("row");
+    }
 }
 
 impl View for SettingsView {
@@ -150,3 +831,190 @@ impl View for SettingsView {
         Some("generic-ui")
     }
 }
+
+
+pub struct AudioView;
+
+impl AudioView {
+    pub const ROWS: u32 = 15;
+
+    pub fn new<P>(cx: &mut Context, params: P) -> Handle<Self>
+    where
+        P: Lens<Target = Arc<OsClapParams>> + Copy,
+    {
+        Self.build(cx, |cx| {
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Analysis Channel").class("label");
+                ParamSlider::new(cx, params, |params| &params.analysis_channel)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Send Samples").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_send_samples)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Send Transport").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_send_transport)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Send ZCR").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_send_zcr)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Send Onsets").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_send_onsets)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Low/Mid Edge").class("label");
+                ParamSlider::new(cx, params, |params| &params.onset_low_edge)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Mid/High Edge").class("label");
+                ParamSlider::new(cx, params, |params| &params.onset_high_edge)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Low Sensitivity").class("label");
+                ParamSlider::new(cx, params, |params| &params.onset_low_sensitivity)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Mid Sensitivity").class("label");
+                ParamSlider::new(cx, params, |params| &params.onset_mid_sensitivity)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "High Sensitivity").class("label");
+                ParamSlider::new(cx, params, |params| &params.onset_high_sensitivity)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Level CC").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_level_cc)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Level CC Number").class("label");
+                ParamSlider::new(cx, params, |params| &params.level_cc)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Level CC Channel").class("label");
+                ParamSlider::new(cx, params, |params| &params.level_cc_channel)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Level CC Rate").class("label");
+                ParamSlider::new(cx, params, |params| &params.level_cc_rate)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Level Mapping").class("label");
+                ParamSlider::new(cx, params, |params| &params.level_mapping)
+                    .class("widget");
+            })
+            .class("row");
+        })
+    }
+}
+
+impl View for AudioView {
+    fn element(&self) -> Option<&'static str> {
+        Some("generic-ui")
+    }
+}
+
+pub struct ArpView;
+
+impl ArpView {
+    pub const ROWS: u32 = 3;
+
+    pub fn new<P>(cx: &mut Context, params: P) -> Handle<Self>
+    where
+        P: Lens<Target = Arc<OsClapParams>> + Copy,
+    {
+        Self.build(cx, |cx| {
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Arp Rate").class("label");
+                ParamSlider::new(cx, params, |params| &params.arp_rate)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Arp Pattern").class("label");
+                ParamSlider::new(cx, params, |params| &params.arp_pattern)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Arp Octaves").class("label");
+                ParamSlider::new(cx, params, |params| &params.arp_octaves)
+                    .class("widget");
+            })
+            .class("row");
+        })
+    }
+}
+
+impl View for ArpView {
+    fn element(&self) -> Option<&'static str> {
+        Some("generic-ui")
+    }
+}
+
+/// What came in on the receive port, apart from the log of what is sent
+pub struct ReceivedView;
+
+impl ReceivedView {
+    /// Rows above the list
+    pub const ROWS: u32 = 1;
+    pub const LIST_HEIGHT: f32 = 300.0;
+
+    pub fn new<L>(cx: &mut Context, received: L) -> Handle<Self>
+    where
+        L: Lens<Target = Vec<String>>,
+    {
+        Self.build(cx, |cx| {
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Received").class("label");
+                Button::new(
+                    cx,
+                    |cx| cx.emit(OsClapEditorEvent::ClearReceived),
+                    |cx| Label::new(cx, "Clear"),
+                )
+                .width(Pixels(60.0));
+            })
+            .class("row");
+            VirtualList::new(cx, received, 20.0, |cx, _index, item| {
+                Label::new(cx, item).left(Pixels(0.0)).class("label")
+            })
+            .height(Pixels(Self::LIST_HEIGHT))
+            .class("row");
+        })
+    }
+}
+
+impl View for ReceivedView {
+    fn element(&self) -> Option<&'static str> {
+        Some("generic-ui")
+    }
+}
@@ -0,0 +1,1201 @@
+use anyhow::Result;
+use crossbeam_channel::{Receiver, Sender};
+use nih_plug::debug::*;
+use parking_lot::RwLock;
+use rosc::OscPacket;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use rosc::OscType;
+use serialport::SerialPort;
+use std::fmt;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tungstenite::{Message, WebSocket};
+
+/// Largest datagram that fits an ethernet frame without IP fragmentation, a fragmented
+/// datagram is lost entirely if any fragment is
+pub const MAX_UDP_PACKET_SIZE: usize = 1472;
+
+/// All interfaces, the OS picks the one that routes to the destination
+pub const DEFAULT_BIND_ADDRESS: &str = "0.0.0.0";
+
+/// Binding can fail for a moment when the OS is out of ephemeral ports
+const UDP_BIND_ATTEMPTS: usize = 3;
+const UDP_BIND_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// How long a read waits for the receiver, reads only happen while the worker is idle
+const UDP_RECV_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// A receiver that isn't up yet fails fast instead of holding up the worker
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+/// A peer that stops reading counts as dropped rather than blocking the worker for good
+const TCP_WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long to wait between attempts to get a dropped peer back
+const TCP_RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a browser gets to finish the WebSocket handshake before we give up on it
+const WEBSOCKET_HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(500);
+/// How often the accept thread looks for new browsers and whether it should stop
+const WEBSOCKET_ACCEPT_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Most binary frames kept from the browsers while a reply is due, the oldest go first
+const WEBSOCKET_INBOUND_LIMIT: usize = 64;
+
+/// What USB serial boards like the Teensy expect unless they are set up otherwise
+pub const DEFAULT_BAUD_RATE: u32 = 115_200;
+/// A device that stops reading counts as gone rather than blocking the worker for good
+const SERIAL_WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long to wait between attempts to open an unplugged device again
+const SERIAL_REOPEN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// SLIP framing bytes, RFC 1055
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OscTransportMode {
+    Udp,
+    /// Size prefixed packets over a stream, nothing is lost as long as the peer is there
+    Tcp,
+    WebSocket,
+    /// Appends every message to a text or csv file instead of sending it anywhere
+    File,
+    /// SLIP framed packets written to a serial device, for microcontrollers
+    Serial,
+}
+
+impl OscTransportMode {
+    pub fn next(self) -> Self {
+        match self {
+            OscTransportMode::Udp => OscTransportMode::Tcp,
+            OscTransportMode::Tcp => OscTransportMode::WebSocket,
+            OscTransportMode::WebSocket => OscTransportMode::File,
+            OscTransportMode::File => OscTransportMode::Serial,
+            OscTransportMode::Serial => OscTransportMode::Udp,
+        }
+    }
+
+    /// Whether the server address is a host to send to, the others listen or write a file
+    pub fn has_destination(self) -> bool {
+        matches!(self, OscTransportMode::Udp | OscTransportMode::Tcp)
+    }
+}
+
+impl fmt::Display for OscTransportMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OscTransportMode::Udp => write!(f, "UDP"),
+            OscTransportMode::Tcp => write!(f, "TCP"),
+            OscTransportMode::WebSocket => write!(f, "WebSocket"),
+            OscTransportMode::File => write!(f, "File"),
+            OscTransportMode::Serial => write!(f, "Serial"),
+        }
+    }
+}
+
+/// Something the worker can push encoded OSC packets through
+pub trait OscTransport: Send {
+    fn mode(&self) -> OscTransportMode;
+    /// Point the transport at a new destination
+    fn connect(&mut self, ip: &str, port: u16) -> Result<()>;
+    /// How many routers packets may cross, transports that don't use IP hops ignore it
+    fn set_ttl(&mut self, _ttl: u32) -> Result<()> {
+        Ok(())
+    }
+    /// Speed of the device, transports that aren't serial ignore it
+    fn set_baud_rate(&mut self, _baud_rate: u32) -> Result<()> {
+        Ok(())
+    }
+    /// More places every packet goes to next to the one connected to, transports that only
+    /// have one destination ignore them
+    fn set_extra_destinations(&mut self, _destinations: Vec<SocketAddr>) {}
+    /// Send a single encoded OSC packet, returns the number of bytes written
+    fn send(&mut self, buf: &[u8]) -> Result<usize>;
+    /// A packet was given up on before it got to `send`, it is only counted
+    fn dropped(&mut self) {}
+    /// Largest packet that can be sent in one piece, `None` if there is no limit
+    fn max_packet_size(&self) -> Option<usize> {
+        None
+    }
+    /// Read one packet the receiver sent back, `None` if nothing is waiting
+    fn try_recv(&mut self, _buf: &mut [u8]) -> Result<Option<usize>> {
+        Ok(None)
+    }
+    /// Whether a reply is due, transports that buffer what the receiver sends only keep it
+    /// while this is on
+    fn set_receiving(&mut self, _receiving: bool) {}
+    /// Why nothing can be sent right now, for transports that can lose their peer
+    fn connection_error(&self) -> Option<&str> {
+        None
+    }
+    /// Called before the worker exits so connections can be shut down cleanly
+    fn close(&mut self) {}
+}
+
+/// Check a destination as it was typed, nothing is looked up so this is cheap enough for every
+/// edit. Host names are only resolved when connecting
+pub fn check_destination(addr: &str, port: u16) -> Result<(), String> {
+    let addr = addr.trim();
+    if addr.is_empty() {
+        return Err("No server address".to_string());
+    }
+    if port == 0 {
+        return Err("Port 0 is not a valid destination".to_string());
+    }
+    match addr.parse::<IpAddr>() {
+        Ok(IpAddr::V4(_)) => Ok(()),
+        //The UDP socket is bound to an IPv4 address so it can only reach IPv4 destinations
+        Ok(IpAddr::V6(_)) => Err(format!("{} has no IPv4 address", addr)),
+        Err(_) if is_host_name(addr) => Ok(()),
+        Err(_) => Err(format!("{} is not a host name", addr)),
+    }
+}
+
+/// Dot separated labels of letters, digits and dashes, a trailing dot is allowed
+fn is_host_name(addr: &str) -> bool {
+    let addr = addr.strip_suffix('.').unwrap_or(addr);
+    addr.len() <= 253
+        && addr.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Resolve a destination to the address packets go to, host names are looked up so this can
+/// block and is left to the worker
+pub fn validate_destination(addr: &str, port: u16) -> Result<SocketAddr, String> {
+    check_destination(addr, port)?;
+    let addr = addr.trim();
+    let resolved = (addr, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Can't resolve {}: {}", addr, e))?;
+    //The UDP socket is bound to an IPv4 address so it can only reach IPv4 destinations
+    resolved
+        .into_iter()
+        .find(|resolved| resolved.is_ipv4())
+        .ok_or_else(|| format!("{} has no IPv4 address", addr))
+}
+
+/// A port typed into the editor, 0 is refused since it would let the OS pick one
+pub fn parse_port(text: &str) -> Result<u16, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("No port".to_string());
+    }
+    let port = text
+        .parse::<u32>()
+        .map_err(|_| format!("{} is not a port", text))?;
+    match port {
+        0 => Err("Port 0 is not a valid destination".to_string()),
+        1..=65535 => Ok(port as u16),
+        _ => Err(format!("Port {} is above 65535", port)),
+    }
+}
+
+/// An extra destination as `host:port`, split without looking anything up
+fn split_destination(destination: &str) -> Result<(&str, u16), String> {
+    let destination = destination.trim();
+    let (addr, port) = destination
+        .rsplit_once(':')
+        .ok_or_else(|| format!("{} has no port", destination))?;
+    let port = port
+        .parse::<u16>()
+        .map_err(|_| format!("{} is not a port", port))?;
+    Ok((addr, port))
+}
+
+/// An extra destination checked like the server address in `check_destination`
+pub fn check_extra_destination(destination: &str) -> Result<(), String> {
+    let (addr, port) = split_destination(destination)?;
+    check_destination(addr, port)
+}
+
+/// An extra destination resolved like the server address in `validate_destination`
+pub fn parse_destination(destination: &str) -> Result<SocketAddr, String> {
+    let (addr, port) = split_destination(destination)?;
+    validate_destination(addr, port)
+}
+
+/// The extra destinations that can be reached, the others are logged and left out so they
+/// don't hold up the rest
+pub fn resolve_destinations(destinations: &[String]) -> Vec<SocketAddr> {
+    destinations
+        .iter()
+        .filter_map(|destination| match parse_destination(destination) {
+            Ok(resolved) => Some(resolved),
+            Err(e) => {
+                nih_error!("Skipping destination {} {}", destination, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// The local address UDP packets are sent from, it has to be one of our own IPv4 addresses but
+/// only binding tells which those are
+pub fn validate_bind_address(addr: &str) -> Result<Ipv4Addr, String> {
+    addr.trim()
+        .parse::<Ipv4Addr>()
+        .map_err(|_| format!("{} is not an IPv4 address", addr.trim()))
+}
+
+/// The file transport takes the path in place of the server address, the file itself is
+/// created on connect
+pub fn validate_dump_path(path: &str) -> Result<(), String> {
+    let path = Path::new(path.trim());
+    if path.as_os_str().is_empty() {
+        return Err("No file path".to_string());
+    }
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => {
+            Err(format!("{} is not a folder", parent.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The serial transport takes the device path in place of the server address, whether the
+/// device is there is only known once it is opened
+pub fn validate_serial_path(path: &str) -> Result<(), String> {
+    if path.trim().is_empty() {
+        return Err("No serial device".to_string());
+    }
+    Ok(())
+}
+
+/// Broadcast and multicast reach every listener on the network, worth pointing out. Only the
+/// limited broadcast address is recognised, a subnet broadcast needs the netmask to tell
+pub fn destination_kind(destination: &IpAddr) -> &'static str {
+    match destination {
+        IpAddr::V4(v4) if v4.is_broadcast() => "broadcast",
+        _ if destination.is_multicast() => "multicast",
+        _ => "unicast",
+    }
+}
+
+/// A transport that isn't pointed anywhere yet, only fails when the OS can't give us a socket.
+/// The bind address and port are only used by UDP
+pub fn open_transport(
+    mode: OscTransportMode,
+    ttl: u32,
+    bind_address: &str,
+    bind_port: u16,
+) -> Result<Box<dyn OscTransport>> {
+    let mut transport: Box<dyn OscTransport> = match mode {
+        OscTransportMode::Udp => Box::new(UdpTransport::new(bind_address, bind_port)?),
+        OscTransportMode::Tcp => Box::<TcpTransport>::default(),
+        OscTransportMode::WebSocket => Box::<WebSocketTransport>::default(),
+        OscTransportMode::File => Box::<FileTransport>::default(),
+        OscTransportMode::Serial => Box::<SlipSerialTransport>::default(),
+    };
+    transport.set_ttl(ttl)?;
+    Ok(transport)
+}
+
+pub fn create_transport(
+    mode: OscTransportMode,
+    ip: &str,
+    port: u16,
+    ttl: u32,
+    baud_rate: u32,
+    bind_address: &str,
+    bind_port: u16,
+) -> Result<Box<dyn OscTransport>> {
+    let mut transport = open_transport(mode, ttl, bind_address, bind_port)?;
+    transport.set_baud_rate(baud_rate)?;
+    transport.connect(ip, port)?;
+    Ok(transport)
+}
+
+/// Gets a copy of every packet the worker sends, for consumers in the same process
+pub type PacketObserver = Arc<RwLock<Option<Sender<OscPacket>>>>;
+
+/// What the worker sent since the last reset, counted by the worker and read by the editor
+pub struct SendStats {
+    packets: AtomicU64,
+    bytes: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl SendStats {
+    pub fn new() -> Self {
+        Self {
+            packets: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, result: &Result<usize>) {
+        match result {
+            Ok(len) => {
+                self.packets.fetch_add(1, Ordering::Relaxed);
+                self.bytes.fetch_add(*len as u64, Ordering::Relaxed);
+            }
+            Err(_) => {
+                self.errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn record_dropped(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Packets, bytes and failed or dropped sends
+    pub fn totals(&self) -> (u64, u64, u64) {
+        (
+            self.packets.load(Ordering::Relaxed),
+            self.bytes.load(Ordering::Relaxed),
+            self.errors.load(Ordering::Relaxed),
+        )
+    }
+
+    pub fn reset(&self) {
+        self.packets.store(0, Ordering::Relaxed);
+        self.bytes.store(0, Ordering::Relaxed);
+        self.errors.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Wraps the real transport and copies every packet to the observer before sending it, the
+/// observer sees what goes out even when the send fails. Every send is counted in the stats
+pub struct ObservedTransport {
+    inner: Box<dyn OscTransport>,
+    observer: PacketObserver,
+    stats: Arc<SendStats>,
+}
+
+impl ObservedTransport {
+    pub fn new(
+        inner: Box<dyn OscTransport>,
+        observer: PacketObserver,
+        stats: Arc<SendStats>,
+    ) -> Self {
+        Self {
+            inner,
+            observer,
+            stats,
+        }
+    }
+
+    /// Swap in a transport of another mode, the observer stays
+    pub fn replace(&mut self, inner: Box<dyn OscTransport>) {
+        self.inner = inner;
+    }
+}
+
+impl OscTransport for ObservedTransport {
+    fn mode(&self) -> OscTransportMode {
+        self.inner.mode()
+    }
+
+    fn connect(&mut self, ip: &str, port: u16) -> Result<()> {
+        self.inner.connect(ip, port)
+    }
+
+    fn set_ttl(&mut self, ttl: u32) -> Result<()> {
+        self.inner.set_ttl(ttl)
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        self.inner.set_baud_rate(baud_rate)
+    }
+
+    fn set_extra_destinations(&mut self, destinations: Vec<SocketAddr>) {
+        self.inner.set_extra_destinations(destinations)
+    }
+
+    fn send(&mut self, buf: &[u8]) -> Result<usize> {
+        if let Some(observer) = self.observer.read().as_ref() {
+            //Decoded from the bytes so split bundles and hellos show up exactly as sent
+            match rosc::decoder::decode_udp(buf) {
+                Ok((_, packet)) => {
+                    //A full observer misses packets instead of holding up the worker
+                    if observer.try_send(packet).is_err() {
+                        nih_trace!("Packet observer is full or gone");
+                    }
+                }
+                Err(e) => nih_error!("Failed to decode packet for the observer {:?}", e),
+            }
+        }
+        let result = self.inner.send(buf);
+        self.stats.record(&result);
+        result
+    }
+
+    fn dropped(&mut self) {
+        self.stats.record_dropped();
+    }
+
+    fn max_packet_size(&self) -> Option<usize> {
+        self.inner.max_packet_size()
+    }
+
+    fn try_recv(&mut self, buf: &mut [u8]) -> Result<Option<usize>> {
+        self.inner.try_recv(buf)
+    }
+
+    fn set_receiving(&mut self, receiving: bool) {
+        self.inner.set_receiving(receiving)
+    }
+
+    fn connection_error(&self) -> Option<&str> {
+        self.inner.connection_error()
+    }
+
+    fn close(&mut self) {
+        self.inner.close()
+    }
+}
+
+/// The socket isn't connected so one socket can send to every destination
+pub struct UdpTransport {
+    socket: UdpSocket,
+    /// Where `connect` points, replies are only read from here
+    destination: Option<SocketAddr>,
+    extra_destinations: Vec<SocketAddr>,
+    /// Joined on the bound interface, all of them for the default bind
+    interface: Ipv4Addr,
+    /// The multicast group of the main destination
+    group: Option<Ipv4Addr>,
+}
+
+impl UdpTransport {
+    /// Port 0 lets the OS pick a free one
+    pub fn new(bind_address: &str, bind_port: u16) -> Result<Self> {
+        let bind_address = validate_bind_address(bind_address).map_err(anyhow::Error::msg)?;
+        let socket = bind_udp(bind_address, bind_port)?;
+        socket.set_broadcast(true)?;
+        socket.set_read_timeout(Some(UDP_RECV_TIMEOUT))?;
+        Ok(Self {
+            socket,
+            destination: None,
+            extra_destinations: Vec::new(),
+            interface: bind_address,
+            group: None,
+        })
+    }
+
+    /// A multicast destination is joined so the interface announces the group, the group of
+    /// the last destination is left. Loopback stays on so receivers on this machine that
+    /// joined get the stream too. Unicast and broadcast destinations are sent to as they are
+    fn join_group(&mut self, destination: SocketAddr) -> Result<()> {
+        let group = match destination {
+            SocketAddr::V4(v4) if v4.ip().is_multicast() => Some(*v4.ip()),
+            _ => None,
+        };
+        if group == self.group {
+            return Ok(());
+        }
+        if let Some(old_group) = self.group.take() {
+            if let Err(e) = self.socket.leave_multicast_v4(&old_group, &self.interface) {
+                nih_trace!("Failed to leave multicast group {} {:?}", old_group, e);
+            }
+        }
+        if let Some(group) = group {
+            self.socket
+                .join_multicast_v4(&group, &self.interface)
+                .map_err(|e| anyhow::anyhow!("Can't join multicast group {}: {}", group, e))?;
+            self.socket.set_multicast_loop_v4(true)?;
+            self.group = Some(group);
+        }
+        Ok(())
+    }
+}
+
+fn bind_udp(bind_address: Ipv4Addr, bind_port: u16) -> Result<UdpSocket> {
+    let mut attempt = 1;
+    loop {
+        match UdpSocket::bind((bind_address, bind_port)) {
+            Ok(socket) => return Ok(socket),
+            Err(e) if attempt < UDP_BIND_ATTEMPTS => {
+                nih_error!("Failed to bind UDP socket, attempt {} {:?}", attempt, e);
+                std::thread::sleep(UDP_BIND_RETRY_DELAY);
+                attempt += 1;
+            }
+            Err(e) => anyhow::bail!("Can't bind to {}:{}: {}", bind_address, bind_port, e),
+        }
+    }
+}
+
+impl OscTransport for UdpTransport {
+    fn mode(&self) -> OscTransportMode {
+        OscTransportMode::Udp
+    }
+
+    fn connect(&mut self, ip: &str, port: u16) -> Result<()> {
+        let destination = validate_destination(ip, port).map_err(anyhow::Error::msg)?;
+        self.join_group(destination)?;
+        self.destination = Some(destination);
+        Ok(())
+    }
+
+    /// Multicast has its own hop limit, both are set so the setting works for either
+    fn set_ttl(&mut self, ttl: u32) -> Result<()> {
+        self.socket.set_ttl(ttl)?;
+        self.socket.set_multicast_ttl_v4(ttl)?;
+        Ok(())
+    }
+
+    fn set_extra_destinations(&mut self, destinations: Vec<SocketAddr>) {
+        self.extra_destinations = destinations;
+    }
+
+    /// A destination that fails doesn't keep the packet from the others, only a failed send to
+    /// the main destination is an error
+    fn send(&mut self, buf: &[u8]) -> Result<usize> {
+        let sent = match self.destination {
+            Some(destination) => self.socket.send_to(buf, destination),
+            None => anyhow::bail!("No destination to send to"),
+        };
+        for destination in &self.extra_destinations {
+            if let Err(e) = self.socket.send_to(buf, destination) {
+                nih_trace!("Failed to send to {} {:?}", destination, e);
+            }
+        }
+        Ok(sent?)
+    }
+
+    fn max_packet_size(&self) -> Option<usize> {
+        Some(MAX_UDP_PACKET_SIZE)
+    }
+
+    /// Only packets from the main destination count, replies to a broadcast come from another
+    /// address and are dropped like anything else
+    fn try_recv(&mut self, buf: &mut [u8]) -> Result<Option<usize>> {
+        loop {
+            match self.socket.recv_from(buf) {
+                Ok((len, from)) if Some(from) == self.destination => return Ok(Some(len)),
+                Ok(_) => continue,
+                //A refused connection is the ICMP error of an earlier send, nobody is listening
+                Err(e) if matches!(
+                    e.kind(),
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::ConnectionRefused
+                ) => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// OSC 1.0 stream framing, every packet goes out with its size in front as a 4 byte big endian
+/// int. A peer that drops is connected to again on a later send
+#[derive(Default)]
+pub struct TcpTransport {
+    stream: Option<TcpStream>,
+    destination: Option<SocketAddr>,
+    /// When a stream was last opened, reconnects wait `TCP_RECONNECT_INTERVAL` after it
+    last_attempt: Option<Instant>,
+    /// Why there is no stream
+    error: Option<String>,
+    /// The size and the packet, reused so sending doesn't allocate once it has grown
+    frame: Vec<u8>,
+    /// Bytes read that don't make up a whole packet yet
+    inbound: Vec<u8>,
+}
+
+impl TcpTransport {
+    fn open_stream(&mut self, destination: SocketAddr) -> Result<()> {
+        self.last_attempt = Some(Instant::now());
+        self.inbound.clear();
+        let opened = TcpStream::connect_timeout(&destination, TCP_CONNECT_TIMEOUT).and_then(
+            |stream| {
+                //Packets are small and late ones are worth less than a few more bytes
+                stream.set_nodelay(true)?;
+                stream.set_read_timeout(Some(UDP_RECV_TIMEOUT))?;
+                stream.set_write_timeout(Some(TCP_WRITE_TIMEOUT))?;
+                Ok(stream)
+            },
+        );
+        match opened {
+            Ok(stream) => {
+                nih_trace!("TCP connected to {}", destination);
+                self.stream = Some(stream);
+                self.error = None;
+                Ok(())
+            }
+            Err(e) => {
+                self.error = Some(format!("Can't connect to {}: {}", destination, e));
+                Err(e.into())
+            }
+        }
+    }
+
+    /// At most once per `TCP_RECONNECT_INTERVAL`, sends in between fail straight away
+    fn reconnect(&mut self) -> Result<()> {
+        let Some(destination) = self.destination else {
+            anyhow::bail!("No destination to send to");
+        };
+        if self
+            .last_attempt
+            .is_some_and(|last_attempt| last_attempt.elapsed() < TCP_RECONNECT_INTERVAL)
+        {
+            anyhow::bail!("Not connected to {}", destination);
+        }
+        nih_trace!("Reconnecting to {}", destination);
+        self.open_stream(destination)
+    }
+
+    /// A stream that failed part way through a packet can't be framed anymore, it is dropped
+    fn lose_stream(&mut self, reason: String) {
+        nih_error!("{}", reason);
+        self.stream = None;
+        self.inbound.clear();
+        self.error = Some(reason);
+    }
+
+    /// The next whole packet from `inbound`, cut to the size of `buf`
+    fn next_inbound(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let size: [u8; 4] = self.inbound.get(..4)?.try_into().ok()?;
+        let size = u32::from_be_bytes(size) as usize;
+        let packet = self.inbound.get(4..4 + size)?;
+        let len = size.min(buf.len());
+        buf[..len].copy_from_slice(&packet[..len]);
+        self.inbound.drain(..4 + size);
+        Some(len)
+    }
+}
+
+impl OscTransport for TcpTransport {
+    fn mode(&self) -> OscTransportMode {
+        OscTransportMode::Tcp
+    }
+
+    fn connect(&mut self, ip: &str, port: u16) -> Result<()> {
+        self.close();
+        let destination = validate_destination(ip, port).map_err(anyhow::Error::msg)?;
+        self.destination = Some(destination);
+        self.open_stream(destination)
+    }
+
+    /// The size and the packet are written in one go so they leave in the same segment
+    fn send(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.stream.is_none() {
+            self.reconnect()?;
+        }
+        self.frame.clear();
+        self.frame.extend_from_slice(&(buf.len() as u32).to_be_bytes());
+        self.frame.extend_from_slice(buf);
+        let written = match &mut self.stream {
+            Some(stream) => stream.write_all(&self.frame),
+            None => anyhow::bail!("Not connected"),
+        };
+        if let Err(e) = written {
+            self.lose_stream(format!("Lost connection: {}", e));
+            return Err(e.into());
+        }
+        Ok(buf.len())
+    }
+
+    fn try_recv(&mut self, buf: &mut [u8]) -> Result<Option<usize>> {
+        if let Some(len) = self.next_inbound(buf) {
+            return Ok(Some(len));
+        }
+        let Some(stream) = &mut self.stream else {
+            return Ok(None);
+        };
+        let mut chunk = [0u8; 1024];
+        match stream.read(&mut chunk) {
+            Ok(0) => {
+                self.lose_stream("Lost connection: closed by the receiver".to_string());
+                Ok(None)
+            }
+            Ok(len) => {
+                self.inbound.extend_from_slice(&chunk[..len]);
+                Ok(self.next_inbound(buf))
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(None),
+            Err(e) => {
+                self.lose_stream(format!("Lost connection: {}", e));
+                Err(e.into())
+            }
+        }
+    }
+
+    fn connection_error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn close(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+        self.inbound.clear();
+        self.error = None;
+    }
+}
+
+/// Runs a small WebSocket server and sends every packet as a binary frame to all connected
+/// browsers. The server address is ignored, we listen on all interfaces on the given port.
+#[derive(Default)]
+pub struct WebSocketTransport {
+    acceptor: Option<WebSocketAcceptor>,
+    clients: Vec<WebSocket<TcpStream>>,
+    /// Binary frames the browsers sent us, waiting for `try_recv`
+    inbound: VecDeque<Vec<u8>>,
+    /// Frames are only kept while a reply is due, everything else is read and dropped
+    receiving: bool,
+}
+
+/// Accepts browsers on a thread of its own, the handshake blocks until the browser answers
+/// and would hold up the worker otherwise
+struct WebSocketAcceptor {
+    clients: Receiver<WebSocket<TcpStream>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WebSocketAcceptor {
+    fn spawn(listener: TcpListener) -> Self {
+        let (sender, clients) = crossbeam_channel::unbounded();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = thread::spawn(move || Self::accept_loop(listener, sender, thread_stop));
+        Self {
+            clients,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    fn accept_loop(
+        listener: TcpListener,
+        clients: Sender<WebSocket<TcpStream>>,
+        stop: Arc<AtomicBool>,
+    ) {
+        while !stop.load(Ordering::Acquire) {
+            let (stream, addr) = match listener.accept() {
+                Ok(client) => client,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(WEBSOCKET_ACCEPT_INTERVAL);
+                    continue;
+                }
+                Err(e) => {
+                    nih_error!("Failed to accept WebSocket client {:?}", e);
+                    thread::sleep(WEBSOCKET_ACCEPT_INTERVAL);
+                    continue;
+                }
+            };
+            //The handshake is blocking, the stream goes non-blocking once it is done
+            let prepared = stream
+                .set_nonblocking(false)
+                .and_then(|_| stream.set_read_timeout(Some(WEBSOCKET_HANDSHAKE_TIMEOUT)));
+            if let Err(e) = prepared {
+                nih_error!("Failed to prepare WebSocket client {} {:?}", addr, e);
+                continue;
+            }
+            match tungstenite::accept(stream) {
+                Ok(client) => {
+                    if let Err(e) = client.get_ref().set_nonblocking(true) {
+                        nih_error!("Failed to prepare WebSocket client {} {:?}", addr, e);
+                        continue;
+                    }
+                    nih_trace!("WebSocket client connected: {}", addr);
+                    if clients.send(client).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => nih_error!("WebSocket handshake with {} failed {:?}", addr, e),
+            }
+        }
+    }
+}
+
+impl Drop for WebSocketAcceptor {
+    /// Waits for the thread so the port is free again for the next listener
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                nih_error!("WebSocket accept thread panicked");
+            }
+        }
+    }
+}
+
+impl WebSocketTransport {
+    fn accept_clients(&mut self) {
+        if let Some(acceptor) = &self.acceptor {
+            self.clients.extend(acceptor.clients.try_iter());
+        }
+    }
+
+    /// Reads whatever the browsers sent us so close frames and pings get answered, binary
+    /// frames go into `inbound` when it is given
+    fn drain_incoming(
+        client: &mut WebSocket<TcpStream>,
+        mut inbound: Option<&mut VecDeque<Vec<u8>>>,
+    ) -> bool {
+        loop {
+            match client.read_message() {
+                Ok(Message::Binary(data)) => {
+                    if let Some(inbound) = inbound.as_deref_mut() {
+                        if inbound.len() >= WEBSOCKET_INBOUND_LIMIT {
+                            inbound.pop_front();
+                        }
+                        inbound.push_back(data);
+                    }
+                }
+                Ok(_) => continue,
+                Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => {
+                    return true
+                }
+                Err(_) => return false,
+            }
+        }
+    }
+
+    fn close_clients(&mut self) {
+        for client in &mut self.clients {
+            let _ = client.close(None);
+            let _ = client.write_pending();
+        }
+        self.clients.clear();
+        self.inbound.clear();
+    }
+}
+
+impl OscTransport for WebSocketTransport {
+    fn mode(&self) -> OscTransportMode {
+        OscTransportMode::WebSocket
+    }
+
+    fn connect(&mut self, _ip: &str, port: u16) -> Result<()> {
+        self.close_clients();
+        //Stopped before binding again, the old thread holds the port until it is done
+        self.acceptor = None;
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+        self.acceptor = Some(WebSocketAcceptor::spawn(listener));
+        Ok(())
+    }
+
+    fn send(&mut self, buf: &[u8]) -> Result<usize> {
+        self.accept_clients();
+        let mut inbound = self.receiving.then_some(&mut self.inbound);
+        self.clients.retain_mut(|client| {
+            if !Self::drain_incoming(client, inbound.as_deref_mut()) {
+                nih_trace!("WebSocket client disconnected");
+                return false;
+            }
+            match client.write_message(Message::Binary(buf.to_vec())) {
+                Ok(_) => true,
+                //The frame is queued and gets flushed on the next write
+                Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => true,
+                Err(tungstenite::Error::SendQueueFull(_)) => {
+                    nih_trace!("WebSocket client too slow, dropping packet");
+                    true
+                }
+                Err(e) => {
+                    nih_trace!("WebSocket client disconnected {:?}", e);
+                    false
+                }
+            }
+        });
+        Ok(buf.len())
+    }
+
+    fn try_recv(&mut self, buf: &mut [u8]) -> Result<Option<usize>> {
+        self.accept_clients();
+        let mut inbound = self.receiving.then_some(&mut self.inbound);
+        self.clients.retain_mut(|client| Self::drain_incoming(client, inbound.as_deref_mut()));
+        Ok(self.inbound.pop_front().map(|data| {
+            let len = data.len().min(buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            len
+        }))
+    }
+
+    fn set_receiving(&mut self, receiving: bool) {
+        self.receiving = receiving;
+        if !receiving {
+            self.inbound.clear();
+        }
+    }
+
+    fn close(&mut self) {
+        self.close_clients();
+    }
+}
+
+/// Writes one line per message with the time it was sent, a `.csv` path gets comma separated
+/// columns and everything else gets spaces. The port is ignored and the file is appended to so
+/// a reconnect doesn't lose what was recorded before.
+#[derive(Default)]
+pub struct FileTransport {
+    writer: Option<BufWriter<File>>,
+    csv: bool,
+    /// Reused for every line so sending doesn't allocate once it has grown
+    line: String,
+}
+
+impl FileTransport {
+    /// Buffered lines only reach the disk here, on reconnect and when the worker exits
+    fn flush(&mut self) {
+        if let Some(writer) = &mut self.writer {
+            if let Err(e) = writer.flush() {
+                nih_error!("Failed to flush OSC dump file {:?}", e);
+            }
+        }
+    }
+
+    fn write_packet(&mut self, time: f64, packet: &OscPacket) -> Result<()> {
+        match packet {
+            OscPacket::Message(message) => {
+                let separator = if self.csv { ',' } else { ' ' };
+                self.line.clear();
+                write!(self.line, "{:.6}", time)?;
+                self.line.push(separator);
+                self.push_field(&message.addr);
+                for arg in &message.args {
+                    self.line.push(separator);
+                    let field = format_arg(arg);
+                    self.push_field(&field);
+                }
+                self.line.push('\n');
+                if let Some(writer) = &mut self.writer {
+                    writer.write_all(self.line.as_bytes())?;
+                }
+            }
+            OscPacket::Bundle(bundle) => {
+                for content in &bundle.content {
+                    self.write_packet(time, content)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Quoted in csv when it would otherwise break the columns
+    fn push_field(&mut self, field: &str) {
+        if self.csv && field.contains([',', '"', '\n']) {
+            self.line.push('"');
+            self.line.push_str(&field.replace('"', "\"\""));
+            self.line.push('"');
+        } else {
+            self.line.push_str(field);
+        }
+    }
+}
+
+/// Blobs are written as hex so the file stays plain text
+pub fn format_arg(arg: &OscType) -> String {
+    match arg {
+        OscType::Int(value) => value.to_string(),
+        OscType::Long(value) => value.to_string(),
+        OscType::Float(value) => value.to_string(),
+        OscType::Double(value) => value.to_string(),
+        OscType::String(value) => value.clone(),
+        OscType::Bool(value) => value.to_string(),
+        OscType::Blob(bytes) => bytes.iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        }),
+        OscType::Nil => "nil".to_string(),
+        OscType::Inf => "inf".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+impl OscTransport for FileTransport {
+    fn mode(&self) -> OscTransportMode {
+        OscTransportMode::File
+    }
+
+    fn connect(&mut self, path: &str, _port: u16) -> Result<()> {
+        self.close();
+        let path = Path::new(path.trim());
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        self.csv = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+        let mut writer = BufWriter::new(file);
+        //A header only for a new file, appending to an old recording keeps its columns
+        if self.csv && writer.get_ref().metadata()?.len() == 0 {
+            writer.write_all(b"time,address,args\n")?;
+        }
+        self.writer = Some(writer);
+        Ok(())
+    }
+
+    fn send(&mut self, buf: &[u8]) -> Result<usize> {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let (_, packet) = rosc::decoder::decode_udp(buf)
+            .map_err(|e| anyhow::anyhow!("Failed to decode packet {:?}", e))?;
+        self.write_packet(time, &packet)?;
+        Ok(buf.len())
+    }
+
+    fn close(&mut self) {
+        self.flush();
+        self.writer = None;
+    }
+}
+
+/// SLIP framed packets written to a serial device, the way microcontrollers read OSC over
+/// USB serial. A device that is unplugged is opened again on a later send
+pub struct SlipSerialTransport {
+    port: Option<Box<dyn SerialPort>>,
+    path: Option<String>,
+    baud_rate: u32,
+    /// When the device was last opened, reopening waits `SERIAL_REOPEN_INTERVAL` after it
+    last_attempt: Option<Instant>,
+    /// Why there is no device
+    error: Option<String>,
+    /// The encoded packet, reused so sending doesn't allocate once it has grown
+    frame: Vec<u8>,
+}
+
+impl Default for SlipSerialTransport {
+    fn default() -> Self {
+        Self {
+            port: None,
+            path: None,
+            baud_rate: DEFAULT_BAUD_RATE,
+            last_attempt: None,
+            error: None,
+            frame: Vec::new(),
+        }
+    }
+}
+
+impl SlipSerialTransport {
+    fn open_port(&mut self, path: &str) -> Result<()> {
+        self.last_attempt = Some(Instant::now());
+        match serialport::new(path, self.baud_rate)
+            .timeout(SERIAL_WRITE_TIMEOUT)
+            .open()
+        {
+            Ok(port) => {
+                nih_trace!("Serial device {} opened at {} baud", path, self.baud_rate);
+                self.port = Some(port);
+                self.error = None;
+                Ok(())
+            }
+            Err(e) => {
+                self.error = Some(format!("Can't open {}: {}", path, e));
+                Err(e.into())
+            }
+        }
+    }
+
+    /// At most once per `SERIAL_REOPEN_INTERVAL`, sends in between fail straight away
+    fn reopen(&mut self) -> Result<()> {
+        let Some(path) = self.path.clone() else {
+            anyhow::bail!("No serial device to send to");
+        };
+        if self
+            .last_attempt
+            .is_some_and(|last_attempt| last_attempt.elapsed() < SERIAL_REOPEN_INTERVAL)
+        {
+            anyhow::bail!("{} is not open", path);
+        }
+        nih_trace!("Reopening {}", path);
+        self.open_port(&path)
+    }
+}
+
+/// An END on both sides, the leading one ends whatever line noise came before the packet
+fn slip_encode(packet: &[u8], frame: &mut Vec<u8>) {
+    frame.clear();
+    frame.push(SLIP_END);
+    for &byte in packet {
+        match byte {
+            SLIP_END => frame.extend_from_slice(&[SLIP_ESC, SLIP_ESC_END]),
+            SLIP_ESC => frame.extend_from_slice(&[SLIP_ESC, SLIP_ESC_ESC]),
+            byte => frame.push(byte),
+        }
+    }
+    frame.push(SLIP_END);
+}
+
+impl OscTransport for SlipSerialTransport {
+    fn mode(&self) -> OscTransportMode {
+        OscTransportMode::Serial
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        if baud_rate == 0 {
+            anyhow::bail!("Baud rate 0 is not valid");
+        }
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn connect(&mut self, path: &str, _port: u16) -> Result<()> {
+        self.close();
+        validate_serial_path(path).map_err(anyhow::Error::msg)?;
+        let path = path.trim().to_string();
+        self.path = Some(path.clone());
+        self.open_port(&path)
+    }
+
+    fn send(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.port.is_none() {
+            self.reopen()?;
+        }
+        slip_encode(buf, &mut self.frame);
+        let written = match &mut self.port {
+            Some(port) => port.write_all(&self.frame).and_then(|_| port.flush()),
+            None => anyhow::bail!("Not connected"),
+        };
+        //Mostly the device being unplugged, it is opened again once it is back
+        if let Err(e) = written {
+            let reason = format!("Lost serial device: {}", e);
+            nih_error!("{}", reason);
+            self.port = None;
+            self.error = Some(reason);
+            return Err(e.into());
+        }
+        Ok(buf.len())
+    }
+
+    fn connection_error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn close(&mut self) {
+        self.port = None;
+        self.error = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slip_frames_the_packet() {
+        let mut frame = Vec::new();
+        slip_encode(&[1, 2, 3], &mut frame);
+        assert_eq!(frame, vec![SLIP_END, 1, 2, 3, SLIP_END]);
+    }
+
+    #[test]
+    fn slip_escapes_end_and_esc() {
+        let mut frame = vec![0xFF; 4];
+        slip_encode(&[SLIP_END, 7, SLIP_ESC], &mut frame);
+        assert_eq!(
+            frame,
+            vec![SLIP_END, SLIP_ESC, SLIP_ESC_END, 7, SLIP_ESC, SLIP_ESC_ESC, SLIP_END]
+        );
+        //An empty packet is still a frame of its own
+        slip_encode(&[], &mut frame);
+        assert_eq!(frame, vec![SLIP_END, SLIP_END]);
+    }
+}
@@ -0,0 +1,52 @@
+/// Enough for every note on one channel, more only allocates in the audio thread
+const ACTIVE_VOICES_CAPACITY: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Voice {
+    pub voice_id: Option<i32>,
+    pub channel: u8,
+    pub note: u8,
+}
+
+impl Voice {
+    /// The host can end a voice by its id or by its key, without an id only the key is left
+    fn matches(&self, voice_id: Option<i32>, channel: u8, note: u8) -> bool {
+        match (self.voice_id, voice_id) {
+            (Some(id), Some(other)) => id == other,
+            _ => self.channel == channel && self.note == note,
+        }
+    }
+}
+
+/// Notes the host is holding, per-voice events go out to every one of them
+pub struct Voices {
+    active: Vec<Voice>,
+}
+
+impl Default for Voices {
+    fn default() -> Self {
+        Self {
+            active: Vec::with_capacity(ACTIVE_VOICES_CAPACITY),
+        }
+    }
+}
+
+impl Voices {
+    /// A retriggered key replaces the voice that was playing it
+    pub fn start(&mut self, voice: Voice) {
+        self.end(voice.voice_id, voice.channel, voice.note);
+        self.active.push(voice);
+    }
+
+    pub fn end(&mut self, voice_id: Option<i32>, channel: u8, note: u8) {
+        self.active.retain(|voice| !voice.matches(voice_id, channel, note));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Voice> {
+        self.active.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+}